@@ -0,0 +1,52 @@
+use eframe::egui::{CentralPanel, Context, ScrollArea, SidePanel, Ui};
+
+use crate::controls::Controls;
+
+/// A standalone window with the controls in a side panel and the user's content in the
+/// middle, for tuning tools that don't need an app of their own.
+///
+/// ```ignore
+/// ControlsApp::new(Controls::new(), |controls, ui| {
+///   controls.group("fog", |g| g.float(ui, "density", 0.1, 0.0..=1.0));
+/// }, |controls, ui| {
+///   ui.label(format!("{:?}", controls["fog"]["density"]));
+/// }).run("Fog")
+/// ```
+pub struct ControlsApp {
+  pub controls: Controls,
+  panel: Box<dyn FnMut(&mut Controls, &mut Ui)>,
+  content: Box<dyn FnMut(&mut Controls, &mut Ui)>,
+}
+
+impl ControlsApp {
+  /// `panel` declares the controls every frame, `content` draws everything else.
+  pub fn new(controls: Controls, panel: impl FnMut(&mut Controls, &mut Ui) + 'static, content: impl FnMut(&mut Controls, &mut Ui) + 'static) -> ControlsApp {
+    ControlsApp {
+      controls,
+      panel: Box::new(panel),
+      content: Box::new(content),
+    }
+  }
+
+  /// Opens the window and blocks until it is closed.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn run(self, title: &str) -> eframe::Result<()> {
+    eframe::run_native(title, eframe::NativeOptions::default(), Box::new(|_| Ok(Box::new(self))))
+  }
+}
+
+impl eframe::App for ControlsApp {
+  fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
+    SidePanel::left("controls").resizable(true).default_width(360.0).show(ctx, |ui| {
+      ScrollArea::vertical().show(ui, |ui| {
+        (self.panel)(&mut self.controls, ui);
+      });
+    });
+    self.controls.tick(ctx);
+    self.controls.flush();
+
+    CentralPanel::default().show(ctx, |ui| {
+      (self.content)(&mut self.controls, ui);
+    });
+  }
+}
@@ -0,0 +1,134 @@
+use crate::controls::{ControlGroup, ControlGroupBuilder, ControlValue};
+
+/// An array of identically structured groups, e.g. one per light, packed as a std140
+/// array of structs.
+#[derive(Debug)]
+pub struct ControlArray {
+  pub name: &'static str,
+  pub elements: Vec<ControlGroup>,
+  template: Vec<(&'static str, ControlValue)>,
+  // set when elements are added or removed, which moves every element after them
+  resized: bool,
+}
+
+impl ControlArray {
+  /// An empty array whose elements start out with the controls and values of `template`.
+  pub fn new(name: &'static str, template: &ControlGroup) -> ControlArray {
+    ControlArray {
+      name,
+      elements: Vec::new(),
+      template: template.values.iter().map(|(name, value)| (*name, value.clone())).collect(),
+      resized: false,
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.elements.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.elements.is_empty()
+  }
+
+  /// Appends an element holding the template's defaults and returns it.
+  pub fn push(&mut self) -> &mut ControlGroup {
+    let mut group = ControlGroup::with_capacity(self.name, self.template.len());
+    for (name, value) in &self.template {
+      group.commit(name, value.clone());
+    }
+    self.elements.push(group);
+    self.resized = true;
+    self.elements.last_mut().unwrap()
+  }
+
+  pub fn remove(&mut self, index: usize) -> ControlGroup {
+    self.resized = true;
+    self.elements.remove(index)
+  }
+
+  /// Bytes between consecutive elements, each element's size rounded up to 16 bytes.
+  pub fn stride(&self) -> usize {
+    self.elements.iter().map(ControlGroup::aligned_size).max().unwrap_or(0)
+  }
+
+  /// True when `pack` would give different bytes than last time.
+  pub fn needs_update(&self) -> bool {
+    self.resized || self.elements.iter().any(|e| e.needs_update)
+  }
+
+  /// Every element back to back, `stride` bytes apart.
+  pub fn pack(&mut self) -> Vec<u8> {
+    let stride = self.stride();
+    let mut buffer = Vec::with_capacity(stride * self.elements.len());
+    for element in &mut self.elements {
+      buffer.extend_from_slice(element.get_bytes());
+      buffer.resize(buffer.len().next_multiple_of(stride.max(1)), 0);
+    }
+    self.resized = false;
+    buffer
+  }
+
+  /// Draws every element with `build`, each under a `name[i]` heading with a remove
+  /// button, followed by a button adding an element.
+  pub fn show(&mut self, ui: &mut eframe::egui::Ui, mut build: impl FnMut(usize, ControlGroupBuilder) -> ControlGroupBuilder) {
+    let mut removed = None;
+    for (i, element) in self.elements.iter_mut().enumerate() {
+      ui.push_id(i, |ui| {
+        ui.horizontal(|ui| {
+          ui.label(format!("{}[{}]", self.name, i));
+          if ui.small_button("✖").clicked() {
+            removed = Some(i);
+          }
+        });
+        build(i, ControlGroupBuilder::new(element));
+      });
+    }
+
+    if let Some(i) = removed {
+      self.remove(i);
+    }
+    if ui.button(format!("+ {}", self.name)).clicked() {
+      self.push();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // a vec4 and a float: 20 bytes of members
+  fn lights() -> ControlArray {
+    let mut light = ControlGroup::new("light");
+    light.commit("color", ControlValue::from([1.0, 1.0, 1.0, 1.0]));
+    light.commit("intensity", ControlValue::from(0.0));
+    ControlArray::new("lights", &light)
+  }
+
+  #[test]
+  fn stride_rounds_elements_up_to_16_bytes() {
+    let mut lights = lights();
+    assert_eq!(lights.stride(), 0);
+    lights.push();
+    assert_eq!(lights.elements[0].member_layout("intensity"), Some((16, 4)));
+    assert_eq!(lights.stride(), 32);
+  }
+
+  #[test]
+  fn removing_an_element_repacks_the_rest() {
+    let mut lights = lights();
+    for i in 0..3 {
+      lights.push().commit("intensity", ControlValue::from(i as f32));
+    }
+    lights.pack();
+    lights.remove(1);
+    assert!(lights.needs_update());
+
+    let stride = lights.stride();
+    let packed = lights.pack();
+    assert_eq!(packed.len(), 2 * stride);
+    assert_eq!(packed[16..20], 0.0f32.to_ne_bytes());
+    assert_eq!(packed[stride + 16..stride + 20], 2.0f32.to_ne_bytes());
+    assert!(!lights.needs_update());
+  }
+}
@@ -0,0 +1,37 @@
+use crate::{controls::{ControlValue, Controls}, dependencies::ControlPath, error::ControlError};
+
+/// A handle to one control, for passing control identities between systems without the
+/// whole `Controls`. Nothing is checked until it is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ControlRef {
+  pub group: &'static str,
+  pub name: &'static str,
+}
+
+impl ControlRef {
+  pub const fn new(group: &'static str, name: &'static str) -> ControlRef {
+    ControlRef { group, name }
+  }
+
+  /// The current value, or `None` if the group or control doesn't exist.
+  pub fn read(&self, controls: &Controls) -> Option<ControlValue> {
+    controls.try_get(self.group).ok()?.try_get(self.name).ok().cloned()
+  }
+
+  /// Commits `value` like an edit would, clamped into the control's declared range, and
+  /// recomputes derived controls. Returns the value the control ended up with. Fails if
+  /// the control doesn't exist or holds a different type.
+  pub fn write(&self, controls: &mut Controls, value: ControlValue) -> Result<ControlValue, ControlError> {
+    controls.set(self.path(), value)
+  }
+
+  pub fn path(&self) -> ControlPath {
+    (self.group, self.name)
+  }
+}
+
+impl From<ControlPath> for ControlRef {
+  fn from((group, name): ControlPath) -> Self {
+    ControlRef { group, name }
+  }
+}
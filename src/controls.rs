@@ -1,8 +1,10 @@
-use std::{collections::BTreeMap, ops::{Deref, Index, IndexMut, RangeInclusive}};
+use std::{collections::{BTreeMap, BTreeSet}, ops::{Deref, Index, IndexMut, RangeInclusive}};
 
-use crate::{persist_order_map::PersistOrderMap, slider::Slider};
+use eframe::egui::{vec2, Color32, DragValue, Grid, Id, KeyboardShortcut, Label, Modifiers, RichText, ScrollArea, Sense, Stroke, Window};
 
-#[derive(Debug)]
+use crate::{control_ref::ControlRef, curve::{self, CurveEditor}, dependencies::{ControlPath, Dependencies}, error::ControlError, gizmo::{self, DirectionGizmo}, gradient::{self, GradientEditor}, hex, knob::Knob, matrix::{Trs2, Trs3}, pad::Pad, serialize::{leak, Migration}, persist_order_map::PersistOrderMap, slider::{Slider, SliderStyle}};
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum ControlValue {
   Int(i32, Vec<u8>, bool),
   Bool(i32, Vec<u8>, bool),
@@ -10,17 +12,37 @@ pub enum ControlValue {
   Vec2([f32; 2], Vec<u8>, bool),
   Vec3([f32; 3], Vec<u8>, bool),
   Vec4([f32; 4], Vec<u8>, bool),
+  // color stops as (position, rgba), packed as a lookup table, see `gradient::lut_bytes`
+  Gradient(Vec<(f32, [f32; 4])>, Vec<u8>, bool),
+  // column-major, each mat3 column padded to a vec4 as std140 lays it out
+  Mat3([[f32; 3]; 3], Vec<u8>, bool),
+  Mat4([[f32; 4]; 4], Vec<u8>, bool),
+  IVec2([i32; 2], Vec<u8>, bool),
+  IVec3([i32; 3], Vec<u8>, bool),
+  IVec4([i32; 4], Vec<u8>, bool),
+  // curve points, packed as one float per sample with four samples to a vec4
+  Curve(Vec<[f32; 2]>, Vec<u8>, bool),
 }
 
 impl ControlValue {
+  /// Size of the packed bytes, which is halved for floats re-encoded with `into_f16`.
   pub fn size(&self) -> usize {
+    self.get_bytes().len()
+  }
+
+  /// Re-encodes the packed bytes of a float or float vector as half floats. The f32 value
+  /// is kept, so editing stays at full precision.
+  #[cfg(feature = "f16")]
+  pub fn into_f16(self) -> ControlValue {
+    fn encode(v: &[f32]) -> Vec<u8> {
+      v.iter().flat_map(|c| half::f16::from_f32(*c).to_ne_bytes()).collect()
+    }
     match self {
-      ControlValue::Int(_, _, _) => std::mem::size_of::<i32>(),
-      ControlValue::Bool(_, _, _) => std::mem::size_of::<i32>(),
-      ControlValue::Float(_, _, _) => std::mem::size_of::<f32>(),
-      ControlValue::Vec2(_, _, _) => std::mem::size_of::<f32>() * 2,
-      ControlValue::Vec3(_, _, _) => std::mem::size_of::<f32>() * 3,
-      ControlValue::Vec4(_, _, _) => std::mem::size_of::<f32>() * 4,
+      ControlValue::Float(v, _, d) => ControlValue::Float(v, encode(&[v]), d),
+      ControlValue::Vec2(v, _, d) => ControlValue::Vec2(v, encode(&v), d),
+      ControlValue::Vec3(v, _, d) => ControlValue::Vec3(v, encode(&v), d),
+      ControlValue::Vec4(v, _, d) => ControlValue::Vec4(v, encode(&v), d),
+      other => other,
     }
   }
 
@@ -32,7 +54,178 @@ impl ControlValue {
       ControlValue::Vec2(_, b, _) => b,
       ControlValue::Vec3(_, b, _) => b,
       ControlValue::Vec4(_, b, _) => b,
+      ControlValue::Gradient(_, b, _) => b,
+      ControlValue::Mat3(_, b, _) => b,
+      ControlValue::Mat4(_, b, _) => b,
+      ControlValue::IVec2(_, b, _) => b,
+      ControlValue::IVec3(_, b, _) => b,
+      ControlValue::IVec4(_, b, _) => b,
+      ControlValue::Curve(_, b, _) => b,
+    }
+  }
+
+  pub fn type_name(&self) -> &'static str {
+    match self {
+      ControlValue::Int(_, _, _) => "int",
+      ControlValue::Bool(_, _, _) => "bool",
+      ControlValue::Float(_, _, _) => "float",
+      ControlValue::Vec2(_, _, _) => "vec2",
+      ControlValue::Vec3(_, _, _) => "vec3",
+      ControlValue::Vec4(_, _, _) => "vec4",
+      ControlValue::Gradient(_, _, _) => "gradient",
+      ControlValue::Mat3(_, _, _) => "mat3",
+      ControlValue::Mat4(_, _, _) => "mat4",
+      ControlValue::IVec2(_, _, _) => "ivec2",
+      ControlValue::IVec3(_, _, _) => "ivec3",
+      ControlValue::IVec4(_, _, _) => "ivec4",
+      ControlValue::Curve(_, _, _) => "curve",
+    }
+  }
+
+  /// Whether the components are integers, rounded when interpolated or set from floats.
+  pub fn is_integer(&self) -> bool {
+    matches!(self, ControlValue::Int(_, _, _) | ControlValue::IVec2(_, _, _) | ControlValue::IVec3(_, _, _) | ControlValue::IVec4(_, _, _))
+  }
+
+  /// Base alignment in bytes under std140: 4 for scalars, 8 for vec2 and 16 for vec3, vec4,
+  /// gradients, curves and matrices.
+  pub fn alignment(&self) -> usize {
+    match self {
+      ControlValue::Int(_, _, _) | ControlValue::Bool(_, _, _) | ControlValue::Float(_, _, _) => 4,
+      ControlValue::Vec2(_, _, _) | ControlValue::IVec2(_, _, _) => 8,
+      ControlValue::Vec3(_, _, _) | ControlValue::Vec4(_, _, _) | ControlValue::Gradient(_, _, _) => 16,
+      ControlValue::IVec3(_, _, _) | ControlValue::IVec4(_, _, _) | ControlValue::Curve(_, _, _) => 16,
+      ControlValue::Mat3(_, _, _) | ControlValue::Mat4(_, _, _) => 16,
+    }
+  }
+
+  pub fn components(&self) -> Vec<f64> {
+    match self {
+      ControlValue::Int(v, _, _) => vec![*v as f64],
+      ControlValue::Bool(v, _, _) => vec![*v as f64],
+      ControlValue::Float(v, _, _) => vec![*v as f64],
+      ControlValue::Vec2(v, _, _) => v.iter().map(|c| *c as f64).collect(),
+      ControlValue::Vec3(v, _, _) => v.iter().map(|c| *c as f64).collect(),
+      ControlValue::Vec4(v, _, _) => v.iter().map(|c| *c as f64).collect(),
+      ControlValue::Gradient(stops, _, _) => stops.iter().flat_map(|(p, c)| std::iter::once(*p).chain(*c)).map(|c| c as f64).collect(),
+      ControlValue::Mat3(m, _, _) => m.iter().flatten().map(|c| *c as f64).collect(),
+      ControlValue::Mat4(m, _, _) => m.iter().flatten().map(|c| *c as f64).collect(),
+      ControlValue::IVec2(v, _, _) => v.iter().map(|c| *c as f64).collect(),
+      ControlValue::IVec3(v, _, _) => v.iter().map(|c| *c as f64).collect(),
+      ControlValue::IVec4(v, _, _) => v.iter().map(|c| *c as f64).collect(),
+      ControlValue::Curve(points, _, _) => points.iter().flatten().map(|c| *c as f64).collect(),
+    }
+  }
+
+  /// Interpolates component-wise towards `other`. Ints round, bools switch at `t = 0.5`, and
+  /// values of different types or shapes (gradients with different stop counts, curves with
+  /// different point counts) give `None`.
+  pub fn lerp(&self, other: &ControlValue, t: f32) -> Option<ControlValue> {
+    if std::mem::discriminant(self) != std::mem::discriminant(other) { return None; }
+    if let ControlValue::Bool(_, _, _) = self {
+      return Some(if t < 0.5 { self.clone() } else { other.clone() });
+    }
+
+    let (a, b) = (self.components(), other.components());
+    if a.len() != b.len() { return None; }
+    let mut value = self.clone();
+    for i in 0..a.len() {
+      let c = a[i] + (b[i] - a[i]) * t as f64;
+      let c = if self.is_integer() { c.round() } else { c };
+      value = value.with_component(i, c)?;
+    }
+    Some(value)
+  }
+
+  /// Clamps each component into the matching range, returning the result only if
+  /// anything had to change.
+  pub fn clamp_components(&self, ranges: &[RangeInclusive<f64>]) -> Option<ControlValue> {
+    let mut value = self.clone();
+    let mut changed = false;
+    for (i, (c, r)) in self.components().into_iter().zip(ranges).enumerate() {
+      let clamped = c.max(r.start().min(*r.end())).min(r.start().max(*r.end()));
+      if clamped != c {
+        value = value.with_component(i, clamped)?;
+        changed = true;
+      }
+    }
+    changed.then_some(value)
+  }
+
+  /// Returns a copy of this value with component `index` replaced, or `None` if out of bounds.
+  pub fn with_component(&self, index: usize, value: f64) -> Option<ControlValue> {
+    match self {
+      ControlValue::Int(_, _, _) if index == 0 => Some(ControlValue::from(value as i32)),
+      ControlValue::Bool(_, _, _) if index == 0 => Some(ControlValue::from(value != 0.0)),
+      ControlValue::Float(_, _, _) if index == 0 => Some(ControlValue::from(value as f32)),
+      ControlValue::Vec2(v, _, _) if index < 2 => { let mut v = *v; v[index] = value as f32; Some(ControlValue::from(v)) },
+      ControlValue::Vec3(v, _, _) if index < 3 => { let mut v = *v; v[index] = value as f32; Some(ControlValue::from(v)) },
+      ControlValue::Vec4(v, _, _) if index < 4 => { let mut v = *v; v[index] = value as f32; Some(ControlValue::from(v)) },
+      // five components per stop: position, then rgba
+      ControlValue::Gradient(stops, _, _) if index < stops.len() * 5 => {
+        let mut stops = stops.clone();
+        let stop = &mut stops[index / 5];
+        match index % 5 {
+          0 => stop.0 = value as f32,
+          c => stop.1[c - 1] = value as f32,
+        }
+        Some(ControlValue::from(stops))
+      },
+      // column-major, like the packed bytes
+      ControlValue::Mat3(m, _, _) if index < 9 => { let mut m = *m; m[index / 3][index % 3] = value as f32; Some(ControlValue::from(m)) },
+      ControlValue::Mat4(m, _, _) if index < 16 => { let mut m = *m; m[index / 4][index % 4] = value as f32; Some(ControlValue::from(m)) },
+      ControlValue::IVec2(v, _, _) if index < 2 => { let mut v = *v; v[index] = value as i32; Some(ControlValue::from(v)) },
+      ControlValue::IVec3(v, _, _) if index < 3 => { let mut v = *v; v[index] = value as i32; Some(ControlValue::from(v)) },
+      ControlValue::IVec4(v, _, _) if index < 4 => { let mut v = *v; v[index] = value as i32; Some(ControlValue::from(v)) },
+      // two components per point, resampled at the packed resolution
+      ControlValue::Curve(points, bytes, _) if index < points.len() * 2 => {
+        let mut points = points.clone();
+        points[index / 2][index % 2] = value as f32;
+        Some(ControlValue::from((points, bytes.len() / 4)))
+      },
+      _ => None,
+    }
+  }
+}
+
+const COMPONENT_NAMES: [&str; 4] = ["x", "y", "z", "w"];
+
+// `x`, `y`, `z`, `w` for vectors, and the index for longer values like matrices
+fn component_name(i: usize, count: usize) -> String {
+  match count <= COMPONENT_NAMES.len() {
+    true => COMPONENT_NAMES[i].to_string(),
+    false => i.to_string(),
+  }
+}
+
+/// A value that generic tools (property editors, scripting bridges, network sync) can
+/// read and write as plain numbers without knowing its concrete type.
+pub trait Inspectable {
+  fn type_name(&self) -> &'static str;
+  fn value_as_f64_slice(&self) -> Vec<f64>;
+  /// Replaces the value's components, keeping its type. Returns false, leaving the value
+  /// untouched, if `values` has the wrong length.
+  fn set_from_f64_slice(&mut self, values: &[f64]) -> bool;
+}
+
+impl Inspectable for ControlValue {
+  fn type_name(&self) -> &'static str {
+    ControlValue::type_name(self)
+  }
+
+  fn value_as_f64_slice(&self) -> Vec<f64> {
+    self.components()
+  }
+
+  fn set_from_f64_slice(&mut self, values: &[f64]) -> bool {
+    if values.len() != self.components().len() { return false; }
+    let mut value = self.clone();
+    for (i, v) in values.iter().enumerate() {
+      let v = if self.is_integer() { v.round() } else { *v };
+      value = value.with_component(i, v).unwrap();
     }
+    *self = value;
+    true
   }
 }
 
@@ -84,6 +277,89 @@ impl From<[f32; 4]> for ControlValue {
   }
 }
 
+impl From<[i32; 2]> for ControlValue {
+  fn from(value: [i32; 2]) -> Self {
+    let bytes = value.iter().flat_map(|c| c.to_ne_bytes()).collect();
+    ControlValue::IVec2(value, bytes, true)
+  }
+}
+
+impl From<[i32; 3]> for ControlValue {
+  fn from(value: [i32; 3]) -> Self {
+    let bytes = value.iter().flat_map(|c| c.to_ne_bytes()).collect();
+    ControlValue::IVec3(value, bytes, true)
+  }
+}
+
+impl From<[i32; 4]> for ControlValue {
+  fn from(value: [i32; 4]) -> Self {
+    let bytes = value.iter().flat_map(|c| c.to_ne_bytes()).collect();
+    ControlValue::IVec4(value, bytes, true)
+  }
+}
+
+/// Curve points and the number of samples to pack.
+impl From<(Vec<[f32; 2]>, usize)> for ControlValue {
+  fn from((points, resolution): (Vec<[f32; 2]>, usize)) -> Self {
+    let bytes = curve::lut_bytes(&points, resolution);
+    ControlValue::Curve(points, bytes, true)
+  }
+}
+
+impl From<[[f32; 3]; 3]> for ControlValue {
+  fn from(columns: [[f32; 3]; 3]) -> Self {
+    let mut bytes = Vec::with_capacity(48);
+    for column in &columns {
+      column.iter().chain(&[0.0]).for_each(|c| bytes.extend_from_slice(&c.to_ne_bytes()));
+    }
+    ControlValue::Mat3(columns, bytes, true)
+  }
+}
+
+impl From<[[f32; 4]; 4]> for ControlValue {
+  fn from(columns: [[f32; 4]; 4]) -> Self {
+    let bytes = columns.iter().flatten().flat_map(|c| c.to_ne_bytes()).collect();
+    ControlValue::Mat4(columns, bytes, true)
+  }
+}
+
+impl From<Vec<(f32, [f32; 4])>> for ControlValue {
+  fn from(stops: Vec<(f32, [f32; 4])>) -> Self {
+    let bytes = gradient::lut_bytes(&stops);
+    ControlValue::Gradient(stops, bytes, true)
+  }
+}
+
+
+// -------------------- control key -------------------- //
+/// Resolves a typed key (usually an enum variant) to the name a group or control is stored under.
+pub trait ControlKey {
+  fn key(&self) -> &'static str;
+}
+
+impl ControlKey for &'static str {
+  fn key(&self) -> &'static str { self }
+}
+
+/// Implements `ControlKey` for an enum by mapping each variant to a name.
+///
+/// ```ignore
+/// enum Light { Intensity, Color }
+/// control_key!(Light { Intensity => "intensity", Color => "color" });
+/// controls["light"][Light::Intensity];
+/// ```
+#[macro_export]
+macro_rules! control_key {
+  ($ty:ident { $($variant:ident => $name:literal),* $(,)? }) => {
+    impl $crate::controls::ControlKey for $ty {
+      fn key(&self) -> &'static str {
+        match self {
+          $($ty::$variant => $name,)*
+        }
+      }
+    }
+  };
+}
 
 // -------------------- group builder -------------------- //
 #[derive(Debug)]
@@ -92,347 +368,3140 @@ pub struct ControlGroup {
   pub values: PersistOrderMap<&'static str, ControlValue>,
   pub packed: Vec<u8>,
   pub needs_update: bool,
+  /// Set along with `needs_update`, but only cleared once the bytes are handed out by
+  /// `Controls::flush` or `Controls::take_dirty`, so packing for any other reason doesn't
+  /// swallow a pending upload.
+  pub upload_dirty: bool,
   pub size: usize,
+  #[cfg(feature = "f16")]
+  pub half: BTreeSet<&'static str>,
+  /// Bool controls fed to the shader as defines instead of being packed.
+  pub defines: BTreeSet<&'static str>,
+  pub defines_changed: bool,
+  /// Vector controls whose components are edited together.
+  pub linked: BTreeSet<&'static str>,
+  pub overrides: BTreeMap<&'static str, Vec<ControlValue>>,
+  /// Per-component ranges of each control, as last declared by the builder.
+  pub ranges: BTreeMap<&'static str, Vec<RangeInclusive<f64>>>,
+  pub reorder_affects_layout: bool,
+  /// Rarely touched controls, only drawn while "Show advanced" is on.
+  pub advanced: BTreeSet<&'static str>,
+  /// Int controls snapped to the nearest power of two whenever they're written.
+  pub pow2: BTreeSet<&'static str>,
+  /// Color of every slider track in the group, unless the control has its own in `tints`.
+  pub tint: Option<Color32>,
+  pub tints: BTreeMap<&'static str, Color32>,
+  /// Packs as all zeros, set while another group is soloed.
+  pub zeroed: bool,
+  /// Smallest change of any component that marks the group for repacking, see
+  /// `set_update_threshold`.
+  pub update_threshold: Option<f64>,
+  // components of each control as of the last pack, what the threshold compares against
+  uploaded: BTreeMap<&'static str, Vec<f64>>,
+  // commits since the last drain, kept only while `Controls::record` is on
+  pub(crate) journal: Option<Vec<(&'static str, ControlValue)>>,
 }
 
 impl ControlGroup {
   pub fn new(name: &'static str) -> ControlGroup {
+    ControlGroup::with_capacity(name, 0)
+  }
+
+  /// A group with room for `capacity` controls, for groups built procedurally in a loop.
+  pub fn with_capacity(name: &'static str, capacity: usize) -> ControlGroup {
     ControlGroup {
       name,
-      values: PersistOrderMap::new(),
+      values: PersistOrderMap::with_capacity(capacity),
       packed: Vec::new(),
       needs_update: false,
+      upload_dirty: false,
       size: 0,
+      #[cfg(feature = "f16")]
+      half: BTreeSet::new(),
+      defines: BTreeSet::new(),
+      defines_changed: false,
+      linked: BTreeSet::new(),
+      overrides: BTreeMap::new(),
+      ranges: BTreeMap::new(),
+      reorder_affects_layout: false,
+      advanced: BTreeSet::new(),
+      pow2: BTreeSet::new(),
+      tint: None,
+      tints: BTreeMap::new(),
+      zeroed: false,
+      update_threshold: None,
+      uploaded: BTreeMap::new(),
+      journal: None,
+    }
+  }
+
+  /// Stores `value` under `name` and marks the group for repacking, unless an update
+  /// threshold is set and no component moved further than it since the last pack.
+  pub fn commit(&mut self, name: &'static str, value: ControlValue) {
+    let value = self.snap(name, value);
+    #[cfg(feature = "f16")]
+    let value = if self.half.contains(name) { value.into_f16() } else { value };
+    let significant = match (self.update_threshold, self.uploaded.get(name)) {
+      (Some(min_delta), Some(uploaded)) => {
+        let components = value.components();
+        components.len() != uploaded.len() || components.iter().zip(uploaded).any(|(a, b)| (a - b).abs() > min_delta)
+      },
+      _ => true,
+    };
+    if let Some(journal) = &mut self.journal {
+      journal.push((name, value.clone()));
+    }
+    self.values.insert(name, value);
+    if significant {
+      self.mark_dirty();
+    }
+    if self.defines.contains(name) {
+      self.defines_changed = true;
+    }
+  }
+
+  // `value` as `name` may hold it, i.e. snapped to a power of two for pow2 controls
+  fn snap(&self, name: &str, value: ControlValue) -> ControlValue {
+    match value {
+      ControlValue::Int(v, _, _) if self.pow2.contains(name) => ControlValue::from(nearest_pow2(v)),
+      value => value,
     }
   }
-  
+
+  /// Marks the group for repacking and uploading.
+  pub fn mark_dirty(&mut self) {
+    self.needs_update = true;
+    self.upload_dirty = true;
+  }
+
+  /// Bytes covered by the packed members, before the trailing padding.
+  pub fn unpadded_size(&self) -> usize {
+    self.layout().last().map_or(0, |(_, offset, size)| offset + size)
+  }
+
+  /// The packed size rounded up to 16 bytes. An empty group packs to nothing.
   pub fn aligned_size(&self) -> usize {
-    let pad = 16 - (self.size % 16);
-    self.size + pad
+    let size = self.unpadded_size();
+    (size + 15) / 16 * 16
+  }
+
+  /// True when nothing gets packed, either because there are no controls or all of them
+  /// are shader defines.
+  pub fn is_empty(&self) -> bool {
+    self.unpadded_size() == 0
   }
 
+  /// The packed bytes, repacked first if anything changed. A group that was never packed
+  /// is packed regardless of `needs_update`, so it can't hand out an empty buffer.
   pub fn get_bytes(&mut self) -> &[u8] {
-    if self.needs_update {
+    if self.needs_update || (self.packed.is_empty() && !self.is_empty()) {
       self.pack();
       self.needs_update = false;
     }
     &self.packed
   }
 
-  fn pack(&mut self) {
-    let mut package = Vec::new();
-    for (_, value) in &self.values {
-      match value {
-        ControlValue::Int(_, b, _) => {
-          package.extend_from_slice(b);
-        },
-        ControlValue::Bool(_, b, _) => {
-          package.extend_from_slice(b);
-        },
-        ControlValue::Float(_, b, _) => {
-          package.extend_from_slice(b);
-        },
-        ControlValue::Vec2(_, b, _) => {
-          package.extend_from_slice(b);
-        },
-        ControlValue::Vec3(_, b, _) => {
-          package.extend_from_slice(b);
-        },
-        ControlValue::Vec4(_, b, _) => {
-          package.extend_from_slice(b);
+  pub fn try_get(&self, key: impl ControlKey) -> Result<&ControlValue, ControlError> {
+    let name = key.key();
+    self.values.get(name).ok_or_else(|| ControlError::UnknownControl(name.to_string()))
+  }
+
+  pub fn try_get_mut(&mut self, key: impl ControlKey) -> Result<&mut ControlValue, ControlError> {
+    let name = key.key();
+    self.values.get_mut(name).ok_or_else(|| ControlError::UnknownControl(name.to_string()))
+  }
+
+  /// Flattens the group into plain numbers for a scripting host. Scalars keep their name,
+  /// vectors are split into `name.x`, `name.y`, ..., matrices and gradients into `name.0`,
+  /// `name.1`, ... and bools become 0 or 1.
+  pub fn to_value_map(&self) -> BTreeMap<String, f64> {
+    let mut map = BTreeMap::new();
+    for (name, value) in &self.values {
+      let components = value.components();
+      if components.len() == 1 {
+        map.insert(name.to_string(), components[0]);
+      } else {
+        let count = components.len();
+        for (i, c) in components.into_iter().enumerate() {
+          map.insert(format!("{}.{}", name, component_name(i, count)), c);
         }
       }
     }
-
-    // pad to 4 bytes
-    let pad = self.aligned_size() - self.size;
-    for _ in 0..pad { package.push(0) }
-    self.packed = package;
+    map
   }
-}
 
-impl Index<&'static str> for ControlGroup {
-  type Output = ControlValue;
-  fn index(&self, name: &'static str) -> &Self::Output { self.values.get(name).unwrap() }
-}
+  /// Writes values keyed like `to_value_map` back into the group. Keys that don't name an
+  /// existing control or component are ignored.
+  pub fn from_value_map(&mut self, map: &BTreeMap<String, f64>) {
+    for (key, v) in map {
+      let (name, index) = match key.rsplit_once('.') {
+        Some((name, c)) => match COMPONENT_NAMES.iter().position(|n| *n == c).or_else(|| c.parse().ok()) {
+          Some(index) => (name, index),
+          None => continue,
+        },
+        None => (key.as_str(), 0),
+      };
 
-impl IndexMut<&'static str> for ControlGroup {
-  fn index_mut(&mut self, name: &'static str) -> &mut Self::Output { self.values.get_mut(name).unwrap() }
-}
+      let Some((&name, value)) = self.values.inner.get_key_value(name) else { continue };
+      if let Some(updated) = value.with_component(index, *v) {
+        self.commit(name, updated);
+      }
+    }
+  }
 
-pub struct ControlGroupBuilder<'a> {
-  group: &'a mut ControlGroup,
-}
+  /// Temporarily packs `value` in place of `name`'s stored value, which is left untouched
+  /// until the matching `pop_override`. Overrides stack.
+  pub fn push_override(&mut self, name: &'static str, value: ControlValue) -> Result<(), ControlError> {
+    let current = self.try_get(name)?;
+    if std::mem::discriminant(current) != std::mem::discriminant(&value) {
+      return Err(ControlError::TypeMismatch { name: name.to_string(), expected: current.type_name() });
+    }
 
-impl<'a> ControlGroupBuilder<'a> {
-  pub fn new(group: &'a mut ControlGroup) -> ControlGroupBuilder<'a> {
-    ControlGroupBuilder {
-      group,
+    let value = self.snap(name, value);
+    #[cfg(feature = "f16")]
+    let value = if self.half.contains(name) { value.into_f16() } else { value };
+    self.overrides.entry(name).or_default().push(value);
+    self.mark_dirty();
+    Ok(())
+  }
+
+  /// Removes the most recent override of `name`, returning it.
+  pub fn pop_override(&mut self, name: &'static str) -> Option<ControlValue> {
+    let stack = self.overrides.get_mut(name)?;
+    let value = stack.pop();
+    if stack.is_empty() {
+      self.overrides.remove(name);
     }
+    self.mark_dirty();
+    value
   }
 
-  pub fn int(self, ui: &mut eframe::egui::Ui, name: &'static str, default: i32, r: RangeInclusive<i32>) -> Self {
-    if !self.group.values.inner.contains_key(&name) {
-      self.group.values.insert(name, ControlValue::from(default));
-      self.group.needs_update = true;
-    };
+  /// Reorders the packed members to avoid std140 padding: vec4s first, then each vec3
+  /// followed by a scalar to fill its last 4 bytes, then vec2s and the remaining scalars.
+  /// Only the pack order changes, controls are still drawn in builder order. **This changes
+  /// the shader-visible layout**, so when the order changes the new `(name, offset, size)`
+  /// layout is returned and the shader's struct must follow it. Returns `None` if the
+  /// order was already optimal.
+  pub fn optimize_layout(&mut self) -> Option<Vec<(&'static str, usize, usize)>> {
+    let (mut vec4s, mut vec3s, mut vec2s, mut scalars) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    for (name, value) in &self.values {
+      if self.defines.contains(name) { continue; }
+      match value {
+        ControlValue::Vec4(_, _, _) | ControlValue::Gradient(_, _, _) => vec4s.push(*name),
+        ControlValue::Mat3(_, _, _) | ControlValue::Mat4(_, _, _) | ControlValue::IVec4(_, _, _) => vec4s.push(*name),
+        ControlValue::Curve(_, _, _) => vec4s.push(*name),
+        ControlValue::Vec3(_, _, _) | ControlValue::IVec3(_, _, _) => vec3s.push(*name),
+        ControlValue::Vec2(_, _, _) | ControlValue::IVec2(_, _, _) => vec2s.push(*name),
+        _ => scalars.push(*name),
+      }
+    }
 
-    let value = self.group.values.get_mut(name).unwrap();
-    let mut value = match value {
-      ControlValue::Int(v, _, _) => *v,
-      _ => panic!("value is not an int"),
-    };
+    let mut order = vec4s;
+    scalars.reverse();
+    for vec3 in vec3s {
+      order.push(vec3);
+      order.extend(scalars.pop());
+    }
+    order.extend(vec2s);
+    order.extend(scalars.into_iter().rev());
 
-    ui.add(Slider::from_get_set(r, |v| {
-      if let Some(v) = v {
-        if v == value { return value; }
-        value = v;
-        self.group.values.insert(name, ControlValue::from(value));
-        self.group.needs_update = true;
-      }
-      return value;
-    }).with_title(name));
+    let defines = self.values.order.iter().filter(|name| self.defines.contains(*name)).copied();
+    let order: Vec<&'static str> = order.into_iter().chain(defines).collect();
+    if order == self.values.order { return None; }
+    self.values.order = order;
+    self.mark_dirty();
+    Some(self.layout())
+  }
 
-    self
+  /// Moves `name` to where `target` is in the pack order, shifting the controls between.
+  pub fn move_control(&mut self, name: &'static str, target: &'static str) {
+    if self.values.move_to(name, target) {
+      self.mark_dirty();
+    }
   }
 
-  pub fn float(self, ui: &mut eframe::egui::Ui, name: &'static str, default: f32, r: RangeInclusive<f32>) -> Self {
-    if !self.group.values.inner.contains_key(&name) {
-      self.group.values.insert(name, ControlValue::from(default));
-      self.group.needs_update = true;
+  /// Renames the control `old` to `new`, keeping its value, pack position and settings.
+  /// Returns false if `old` doesn't exist or `new` already does. Pair with a migration
+  /// so saved documents follow the new name.
+  pub fn rename(&mut self, old: &'static str, new: &'static str) -> bool {
+    if !self.values.rename(old, new) { return false; }
+
+    fn rename_in<V>(map: &mut BTreeMap<&'static str, V>, old: &'static str, new: &'static str) {
+      if let Some(value) = map.remove(old) { map.insert(new, value); }
     }
+    fn rename_in_set(set: &mut BTreeSet<&'static str>, old: &'static str, new: &'static str) {
+      if set.remove(old) { set.insert(new); }
+    }
+    rename_in(&mut self.overrides, old, new);
+    rename_in(&mut self.ranges, old, new);
+    rename_in(&mut self.tints, old, new);
+    rename_in(&mut self.uploaded, old, new);
+    rename_in_set(&mut self.linked, old, new);
+    rename_in_set(&mut self.defines, old, new);
+    rename_in_set(&mut self.advanced, old, new);
+    rename_in_set(&mut self.pow2, old, new);
+    #[cfg(feature = "f16")]
+    rename_in_set(&mut self.half, old, new);
+    true
+  }
 
-    let value = self.group.values.get_mut(name).unwrap();
-    let mut value = match value {
-      ControlValue::Float(v, _, _) => *v,
-      _ => panic!("value is not a float"),
-    };
+  /// Tints the slider tracks of the whole group, to tell related groups apart at a glance.
+  pub fn set_tint(&mut self, color: Color32) {
+    self.tint = Some(color);
+  }
 
-    ui.add(Slider::from_get_set(r, |v| {
-      if let Some(v) = v {
-        if v == value { return value; }
-        value = v;
-        self.group.values.insert(name, ControlValue::from(value));
-        self.group.needs_update = true;
-        println!("{} = {}", name, value);
-      }
-      return value;
-    }).with_title(name));
+  /// Tints the slider track of one control, over the group's tint.
+  pub fn set_control_tint(&mut self, name: &'static str, color: Color32) {
+    self.tints.insert(name, color);
+  }
 
-    self
+  pub fn tint_of(&self, name: &str) -> Option<Color32> {
+    self.tints.get(name).copied().or(self.tint)
   }
 
-  pub fn vec2(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 2], r1: RangeInclusive<f32>, r2: RangeInclusive<f32>) -> Self {
-    if !self.group.values.inner.contains_key(&name) {
-      self.group.values.insert(name, ControlValue::from(default));
-      self.group.needs_update = true;
-    };
+  /// Stops changes smaller than `min_delta` in every component, compared to the values last
+  /// packed, from marking the group for repacking, so noisy animated inputs don't cause an
+  /// upload every frame. The values are still stored, and go out with the next real change.
+  pub fn set_update_threshold(&mut self, min_delta: f64) {
+    self.update_threshold = Some(min_delta);
+  }
 
-    let value = self.group.values.get_mut(name).unwrap();
-    let mut value = match value {
-      ControlValue::Vec2(v, _, _) => *v,
-      _ => panic!("value is not a vec2"),
-    };
+  pub fn is_overridden(&self, name: &str) -> bool {
+    self.overrides.contains_key(name)
+  }
 
-    ui.add(Slider::from_get_set(r1, |v| {
-      if let Some(v) = v {
-        if v == value[0] { return value[0]; }
-        value[0] = v;
-        self.group.values.insert(name, ControlValue::from(value));
-        self.group.needs_update = true;
-      }
-      return value[0];
-    }).with_title(format!("{}.x", name).as_str()));
+  // the value that gets packed for `name`: its latest override, or else the stored value
+  fn effective(&self, name: &str) -> Option<&ControlValue> {
+    self.overrides.get(name).and_then(|stack| stack.last()).or_else(|| self.values.inner.get(name))
+  }
 
-    ui.add(Slider::from_get_set(r2, |v| {
-      if let Some(v) = v {
-        if v == value[1] { return value[1]; }
-        value[1] = v;
-        self.group.values.insert(name, ControlValue::from(value));
-        self.group.needs_update = true;
-      }
-      return value[1];
-    }).with_title(format!("{}.y", name).as_str()));
+  // where each packed member lands in the buffer as (name, offset, size), in pack order.
+  // Members start on their std140 base alignment, e.g. a float followed by a vec3 puts the
+  // vec3 at 16; half floats align to their halved size.
+  fn layout(&self) -> Vec<(&'static str, usize, usize)> {
+    let mut layout = Vec::new();
+    let mut offset = 0;
+    for (name, _) in &self.values {
+      if self.defines.contains(name) { continue; }
+      let value = self.effective(name).unwrap();
+      let size = value.size();
+      offset = offset.next_multiple_of(value.alignment().min(size.next_power_of_two()));
+      layout.push((*name, offset, size));
+      offset += size;
+    }
+    layout
+  }
 
-    self
+  /// Walks the packed buffer, repacking first if needed, as `(name, offset, bytes)` for each
+  /// member in pack order. Gaps between members and the trailing padding come out with a
+  /// `None` name, so the slices cover the whole buffer.
+  pub fn iter_packed(&mut self) -> impl Iterator<Item = (Option<&'static str>, usize, &[u8])> {
+    let layout = self.layout();
+    let packed = self.get_bytes();
+    let mut spans = Vec::with_capacity(layout.len() * 2 + 1);
+    let mut cursor = 0;
+    for (name, offset, size) in layout {
+      if offset > cursor { spans.push((None, cursor, offset - cursor)); }
+      spans.push((Some(name), offset, size));
+      cursor = offset + size;
+    }
+    if packed.len() > cursor { spans.push((None, cursor, packed.len() - cursor)); }
+    spans.into_iter().map(move |(name, offset, size)| (name, offset, &packed[offset..offset + size]))
   }
 
+  /// The `(offset, size)` in bytes of the control `name` within the packed buffer.
+  pub fn member_layout(&self, name: &str) -> Option<(usize, usize)> {
+    self.layout().into_iter()
+      .find(|(n, _, _)| *n == name)
+      .map(|(_, offset, size)| (offset, size))
+  }
 
-  pub fn button(self, ui: &mut eframe::egui::Ui, title: &'static str, mut action: impl FnMut()) -> Self {
-      if ui.button(title).clicked() {
-        action();
+  fn pack(&mut self) {
+    // reuse the previous buffer's allocation
+    let mut package = std::mem::take(&mut self.packed);
+    package.clear();
+    let aligned_size = self.aligned_size();
+    package.reserve(aligned_size);
+    for (name, offset, size) in self.layout() {
+      package.resize(offset, 0);
+      if self.zeroed {
+        package.resize(offset + size, 0);
+      } else {
+        package.extend_from_slice(self.effective(name).unwrap().get_bytes());
       }
-      self
     }
+    self.size = package.len();
 
-}
-
-// -------------------- action group -------------------- //
-pub struct ActionGroup {
-  pub name: &'static str,
-  pub actions: BTreeMap<&'static str, Box<dyn FnMut(&mut Controls)>>,
-}
+    // pad to 16 bytes
+    package.resize(aligned_size, 0);
+    self.packed = package;
 
-impl ActionGroup {
-  pub fn new(name: &'static str) -> ActionGroup {
-    ActionGroup {
-      name,
-      actions: BTreeMap::new(),
+    if self.update_threshold.is_some() {
+      self.uploaded = self.values.iter().map(|(name, value)| (*name, value.components())).collect();
     }
   }
 }
 
+impl<K: ControlKey> Index<K> for ControlGroup {
+  type Output = ControlValue;
+  fn index(&self, key: K) -> &Self::Output { self.try_get(key).unwrap_or_else(|e| panic!("{}", e)) }
+}
 
-pub struct ActionGroupBuilder<'a> {
-  group: &'a mut ActionGroup,
+impl<K: ControlKey> IndexMut<K> for ControlGroup {
+  fn index_mut(&mut self, key: K) -> &mut Self::Output { self.try_get_mut(key).unwrap_or_else(|e| panic!("{}", e)) }
 }
 
-impl<'a> ActionGroupBuilder<'a> {
-  pub fn new(group: &'a mut ActionGroup) -> ActionGroupBuilder<'a> {
-    ActionGroupBuilder {
-      group,
-    }
+// -------------------- selection -------------------- //
+/// Controls picked for bulk editing by shift-clicking their labels. Dragging any selected
+/// control moves every other selected control by the same fraction of its own range.
+#[derive(Debug, Default)]
+pub struct Selection {
+  pub controls: BTreeSet<(&'static str, &'static str)>,
+  frame: u64,
+  // (source, delta) recorded this frame, and the one from last frame that is being applied
+  current: Option<((&'static str, &'static str), f64)>,
+  pending: Option<((&'static str, &'static str), f64)>,
+}
+
+impl Selection {
+  pub fn clear(&mut self) {
+    self.controls.clear();
   }
 
-  pub fn button(self, name: &'static str, action: impl FnMut(&mut Controls) + 'static) -> Self {
-    self.group.actions.insert(name, Box::new(action));
-    self
+  fn sync(&mut self, frame: u64) {
+    if frame != self.frame {
+      self.frame = frame;
+      self.pending = self.current.take();
+    }
   }
 }
-// -------------------- controls -------------------- //
-#[macro_export]
-macro_rules! controls {
-    () => { Controls::new(BTreeMap::<&str, ControlGroup>::new()) };
-    ($($group_name:literal: $group:tt),* $(,)?) => {
-      {
-        let mut map = BTreeMap::<&str, ControlGroup>::new();
-        $(
-          map.insert($group_name, controls!($group_name, $group));
-        )*
-        Controls::new(map)
-      }
-    };
-    ($group_name:literal, {$($name:literal: $properties:tt),* $(,)?}) => {
-      {
-        let mut map = ControlGroup::new($group_name);
-        $(
-          // let control_value = ControlValue::from($value);
-          // map.size += control_value.size();
-          // map.values.insert($name, control_value);
-          controls!($group_name, $name, $properties);
-        )*
-        map.needs_update = true;
-        map
-      }
-    };
-    ($group_name:literal, $name:literal, {$($key:literal: $value:tt),* $(,)?}) => {
-      
-    }
+
+// the power of two closest to `v`, rounding ties down, from 1 up to 2^30
+fn nearest_pow2(v: i32) -> i32 {
+  if v <= 1 { return 1; }
+  let below = 1i32 << (v.ilog2().min(30));
+  if below == 1 << 30 || v - below <= (below << 1) - v { below } else { below << 1 }
 }
 
-#[derive(Debug)]
-pub struct Controls {
-  values: BTreeMap<&'static str, ControlGroup>,
+// float equality that treats two NaNs as the same value, so a control stuck at NaN isn't
+// recommitted and marked dirty every frame
+fn same_float(a: f32, b: f32) -> bool {
+  a == b || (a.is_nan() && b.is_nan())
 }
 
-impl Controls {
-  pub fn new() -> Controls {
-    Controls {
-      values: BTreeMap::new(),
-    }
-  }
+// moves `value` by `delta` times the range's span, keeping it inside the range
+fn shift_in_range(value: f64, delta: f64, start: f64, end: f64) -> f64 {
+  (value + delta * (end - start)).clamp(start.min(end), start.max(end))
+}
+
+fn relative_change(before: f64, after: f64, start: f64, end: f64) -> f64 {
+  if start == end { 0.0 } else { (after - before) / (end - start) }
+}
+
+/// How `vec3_sorted` keeps `x <= y <= z` when a component is dragged past a neighbour.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SortMode {
+  /// The dragged component pushes its neighbours along.
+  #[default]
+  Push,
+  /// The dragged component stops at its neighbours.
+  Clamp,
+}
 
-  pub fn to_json(&self) -> String {
-    // let json = serde_json::to_string(&self.values).unwrap();
-    // let json_string = serde_json::to_string_pretty(&json).unwrap();
-
-    todo!()
-  }
-
-  pub fn save_json(&mut self) {
-    // let js_value = rfd::AsyncFileDialog::new()
-    //   .set_file_name("uniforms.json")
-    //   .save_file();
-    
-    // // if is web
-    // #[cfg(target_arch = "wasm32")] {
-    //   // Spawn the save dialog asynchronously
-    //   wasm_bindgen_futures::spawn_local(async move {
-    //     if let Some(file) = js_value.await {
-    //       // File was saved successfully
-    //       file.write(json_string.as_bytes()).await.unwrap();
-    //     }
-    //   });
-    // }
-
-    // // if is native
-    // #[cfg(not(target_arch = "wasm32"))] {
-    //   // Spawn the save dialog asynchronously
-    //   tokio::runtime::Builder::new_multi_thread()
-    //     .enable_all()
-    //     .build()
-    //     .unwrap()
-    //     .spawn(async move {
-    //       println!("saving");
-    //       if let Some(file) = js_value.await {
-    //         println!("file: {:?}", file);
-    //         // File was saved successfully
-    //         file.write(json_string.as_bytes()).await.unwrap();
-    //       }
-    //     });
-    // }
-    todo!()
+// sets component `i` to `v` while keeping the components in increasing order
+fn set_sorted(value: &mut [f32; 3], i: usize, v: f32, mode: SortMode) {
+  match mode {
+    SortMode::Push => {
+      value[i] = v;
+      for j in 0..i { value[j] = value[j].min(v); }
+      for j in i + 1..3 { value[j] = value[j].max(v); }
+    },
+    SortMode::Clamp => {
+      let lo = if i > 0 { value[i - 1] } else { f32::NEG_INFINITY };
+      let hi = if i < 2 { value[i + 1] } else { f32::INFINITY };
+      value[i] = v.max(lo).min(hi);
+    },
   }
+}
 
-  pub fn group(&mut self, name: &'static str, build: impl FnOnce(ControlGroupBuilder) -> ControlGroupBuilder) -> &mut Self {
-    if !self.values.contains_key(name) {
-      self.values.insert(name, ControlGroup {
-        name,
-        values: PersistOrderMap::new(),
-        packed: Vec::new(),
-        needs_update: false,
-        size: 0,
-      });
+pub struct ControlGroupBuilder<'a> {
+  group: &'a mut ControlGroup,
+  speed_modifier: (Modifiers, f64),
+  selection: Option<&'a mut Selection>,
+  readonly: BTreeSet<&'static str>,
+  visible: bool,
+  show_advanced: bool,
+  sort_mode: SortMode,
+  strict: bool,
+  // the kind each control was declared as during this build
+  declared: BTreeMap<&'static str, &'static str>,
+  quantize_input: bool,
+  others: Option<&'a BTreeMap<&'static str, ControlGroup>>,
+  slider_style: SliderStyle,
+}
+
+impl<'a> ControlGroupBuilder<'a> {
+  pub fn new(group: &'a mut ControlGroup) -> ControlGroupBuilder<'a> {
+    ControlGroupBuilder {
+      group,
+      speed_modifier: (Modifiers::NONE, 1.0),
+      selection: None,
+      readonly: BTreeSet::new(),
+      visible: true,
+      show_advanced: false,
+      sort_mode: SortMode::default(),
+      strict: false,
+      declared: BTreeMap::new(),
+      quantize_input: false,
+      others: None,
+      slider_style: SliderStyle::default(),
     }
+  }
 
-    let group = self.values.get_mut(name).unwrap();
-    let builder = ControlGroupBuilder::new(group);
+  /// Passed on to every slider, see `Slider::style`.
+  pub fn slider_style(mut self, style: SliderStyle) -> Self {
+    self.slider_style = style;
+    self
+  }
 
-    build(builder);
-    
+  /// The other groups, which `mirror` can show controls of.
+  pub fn others(mut self, groups: &'a BTreeMap<&'static str, ControlGroup>) -> Self {
+    self.others = Some(groups);
     self
   }
 
+  /// How the following `vec3_sorted` controls resolve components crossing each other.
+  pub fn sort_mode(mut self, mode: SortMode) -> Self {
+    self.sort_mode = mode;
+    self
+  }
+
+  /// When hidden, controls still register their defaults but draw nothing.
+  pub fn visible(mut self, on: bool) -> Self {
+    self.visible = on;
+    self
+  }
+
+  /// Makes declaring the same control twice in one build with different types an error, to
+  /// catch copy-pasted names. Redeclaring it with the same type is still fine. Off by default.
+  pub fn strict(mut self, on: bool) -> Self {
+    self.strict = on;
+    self
+  }
+
+  // records that `name` is declared as `kind`, e.g. "a float"
+  fn declare(&mut self, name: &'static str, kind: &'static str) -> Result<(), ControlError> {
+    match self.declared.insert(name, kind) {
+      Some(previous) if previous != kind && self.strict => Err(ControlError::DuplicateControl(format!("{}.{}", self.group.name, name))),
+      _ => Ok(()),
+    }
+  }
+
+  /// Passed on to the float sliders that follow, see `Slider::quantize_input`.
+  pub fn quantize_input(mut self, on: bool) -> Self {
+    self.quantize_input = on;
+    self
+  }
+
+  /// Whether controls marked with `advanced` are drawn.
+  pub fn show_advanced(mut self, on: bool) -> Self {
+    self.show_advanced = on;
+    self
+  }
+
+  /// Hides the control `name` unless advanced controls are shown. Call before the
+  /// control's builder method.
+  pub fn advanced(self, name: &'static str) -> Self {
+    self.group.advanced.insert(name);
+    self
+  }
+
+  fn hidden(&self, name: &'static str) -> bool {
+    !self.visible || (!self.show_advanced && self.group.advanced.contains(name))
+  }
+
+  /// Renders the named controls disabled, e.g. because they are computed from other controls.
+  pub fn readonly(mut self, names: BTreeSet<&'static str>) -> Self {
+    self.readonly = names;
+    self
+  }
+
+  /// Enables shift-click selection and bulk editing of this group's numeric controls.
+  pub fn selection(mut self, selection: &'a mut Selection) -> Self {
+    self.selection = Some(selection);
+    self
+  }
+
+  /// Packs the float or float vector `name` as f16. Call before the control's builder method.
+  #[cfg(feature = "f16")]
+  pub fn half(self, name: &'static str) -> Self {
+    self.group.half.insert(name);
+    if let Some(value) = self.group.values.inner.remove(&name) {
+      self.group.commit(name, value);
+    }
+    self
+  }
+
+  /// Marks the bool control `name` as a shader define, see `Controls::specialization_constants`.
+  pub fn shader_define(self, name: &'static str) -> Self {
+    if self.group.defines.insert(name) {
+      self.group.defines_changed = true;
+      self.group.mark_dirty();
+    }
+    self
+  }
+
+  /// Shows drag handles that reorder the controls. **This changes the packed byte order**,
+  /// so only use it when the shader's struct is generated from, or kept in sync with,
+  /// `ControlGroup::member_layout`. Drawing order still follows the builder calls.
+  pub fn reorder_affects_layout(self, on: bool) -> Self {
+    self.group.reorder_affects_layout = on;
+    self
+  }
+
+  /// Passed on to every slider, see `Slider::speed_modifier`.
+  pub fn speed_modifier(mut self, modifier: Modifiers, multiplier: f64) -> Self {
+    self.speed_modifier = (modifier, multiplier);
+    self
+  }
+
+  pub fn int(self, ui: &mut eframe::egui::Ui, name: &'static str, default: i32, r: RangeInclusive<i32>) -> Self {
+    self.try_int(ui, name, default, r).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_int(mut self, ui: &mut eframe::egui::Ui, name: &'static str, default: i32, r: RangeInclusive<i32>) -> Result<Self, ControlError> {
+    self.declare(name, "an int")?;
+    if !self.group.values.inner.contains_key(&name) {
+      self.group.commit(name, ControlValue::from(default));
+    };
+
+    let value = self.group.values.get_mut(name).unwrap();
+    let mut value = match value {
+      ControlValue::Int(v, _, _) => *v,
+      _ => return Err(ControlError::TypeMismatch { name: name.to_string(), expected: "an int" }),
+    };
+    self.group.ranges.insert(name, vec![*r.start() as f64..=*r.end() as f64]);
+    if self.hidden(name) { return Ok(self); }
+
+    let (start, end) = (*r.start() as f64, *r.end() as f64);
+    let (mut selected, bulk) = self.bulk_begin(ui, name);
+    if let Some(delta) = bulk {
+      value = shift_in_range(value as f64, delta, start, end).round() as i32;
+      self.group.commit(name, ControlValue::from(value));
+    }
+    let before = value;
+
+    let (modifier, multiplier) = self.speed_modifier;
+    let enabled = !self.readonly.contains(name) && !self.group.is_overridden(name);
+    let tint = self.group.tint_of(name);
+    let (group_name, reorder) = (self.group.name, self.group.reorder_affects_layout);
+    let layout = self.group.member_layout(name);
+    let slider = Slider::from_get_set(r, |v| {
+      if let Some(v) = v {
+        if v == value { return value; }
+        value = v;
+        self.group.commit(name, ControlValue::from(value));
+      }
+      return value;
+    }).with_title(name).speed_modifier(modifier, multiplier).tint(tint).style(self.slider_style).selectable(&mut selected);
+    let dropped = reorder_row(ui, reorder, group_name, name, |ui| { copy_path_menu(&ui.add_enabled(enabled, slider), group_name, name, layout); });
+    if let Some(dropped) = dropped {
+      self.group.move_control(dropped, name);
+    }
+
+    self.bulk_end(ui, name, selected, relative_change(before as f64, value as f64, start, end));
+    Ok(self)
+  }
+
+  /// A checkbox, packed as an int holding 0 or 1 like a GLSL `bool` uniform.
+  pub fn bool(self, ui: &mut eframe::egui::Ui, name: &'static str, default: bool) -> Self {
+    self.try_bool(ui, name, default).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_bool(mut self, ui: &mut eframe::egui::Ui, name: &'static str, default: bool) -> Result<Self, ControlError> {
+    self.declare(name, "a bool")?;
+    if !self.group.values.inner.contains_key(&name) {
+      self.group.commit(name, ControlValue::from(default));
+    };
+
+    let value = self.group.values.get_mut(name).unwrap();
+    let mut value = match value {
+      ControlValue::Bool(v, _, _) => *v != 0,
+      _ => return Err(ControlError::TypeMismatch { name: name.to_string(), expected: "a bool" }),
+    };
+    if self.hidden(name) { return Ok(self); }
+
+    let enabled = !self.readonly.contains(name) && !self.group.is_overridden(name);
+    let (group_name, reorder) = (self.group.name, self.group.reorder_affects_layout);
+    let layout = self.group.member_layout(name);
+    let mut changed = false;
+    let dropped = reorder_row(ui, reorder, group_name, name, |ui| {
+      let response = ui.add_enabled(enabled, eframe::egui::Checkbox::new(&mut value, name));
+      changed = response.changed();
+      copy_path_menu(&response, group_name, name, layout);
+    });
+    if changed {
+      self.group.commit(name, ControlValue::from(value));
+    }
+    if let Some(dropped) = dropped {
+      self.group.move_control(dropped, name);
+    }
+    Ok(self)
+  }
+
+  /// An int picked by label from a dropdown, e.g. `&[("Phong", 0), ("PBR", 1)]` for a
+  /// shading model. A stored value matching no option shows as the number itself.
+  pub fn enumeration(self, ui: &mut eframe::egui::Ui, name: &'static str, default: i32, options: &[(&str, i32)]) -> Self {
+    self.try_enumeration(ui, name, default, options).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_enumeration(mut self, ui: &mut eframe::egui::Ui, name: &'static str, default: i32, options: &[(&str, i32)]) -> Result<Self, ControlError> {
+    self.declare(name, "an int")?;
+    if !self.group.values.inner.contains_key(&name) {
+      self.group.commit(name, ControlValue::from(default));
+    };
+
+    let value = self.group.values.get_mut(name).unwrap();
+    let mut value = match value {
+      ControlValue::Int(v, _, _) => *v,
+      _ => return Err(ControlError::TypeMismatch { name: name.to_string(), expected: "an int" }),
+    };
+    let values = options.iter().map(|(_, v)| *v);
+    if let (Some(min), Some(max)) = (values.clone().min(), values.max()) {
+      self.group.ranges.insert(name, vec![min as f64..=max as f64]);
+    }
+    if self.hidden(name) { return Ok(self); }
+
+    let enabled = !self.readonly.contains(name) && !self.group.is_overridden(name);
+    let (group_name, reorder) = (self.group.name, self.group.reorder_affects_layout);
+    let layout = self.group.member_layout(name);
+    let before = value;
+    let selected = options.iter().find(|(_, v)| *v == value).map_or_else(|| value.to_string(), |(label, _)| label.to_string());
+    let dropped = reorder_row(ui, reorder, group_name, name, |ui| {
+      ui.add_enabled_ui(enabled, |ui| {
+        ui.horizontal(|ui| {
+          let response = eframe::egui::ComboBox::from_id_salt((group_name, name)).selected_text(selected).show_ui(ui, |ui| {
+            for (label, v) in options {
+              ui.selectable_value(&mut value, *v, *label);
+            }
+          }).response;
+          ui.label(name);
+          copy_path_menu(&response, group_name, name, layout);
+        });
+      });
+    });
+
+    if value != before {
+      self.group.commit(name, ControlValue::from(value));
+    }
+    if let Some(dropped) = dropped {
+      self.group.move_control(dropped, name);
+    }
+    Ok(self)
+  }
+
+  /// An angle edited in degrees on a knob and a drag value, stored and packed as radians
+  /// in `0..2π`, e.g. for a rotation uniform.
+  pub fn angle(self, ui: &mut eframe::egui::Ui, name: &'static str, default_degrees: f32) -> Self {
+    self.try_angle(ui, name, default_degrees).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_angle(mut self, ui: &mut eframe::egui::Ui, name: &'static str, default_degrees: f32) -> Result<Self, ControlError> {
+    self.declare(name, "a float")?;
+    if !self.group.values.inner.contains_key(&name) {
+      self.group.commit(name, ControlValue::from(default_degrees.rem_euclid(360.0).to_radians()));
+    };
+
+    let value = self.group.values.get_mut(name).unwrap();
+    let radians = match value {
+      ControlValue::Float(v, _, _) => *v,
+      _ => return Err(ControlError::TypeMismatch { name: name.to_string(), expected: "a float" }),
+    };
+    self.group.ranges.insert(name, vec![0.0..=std::f64::consts::TAU]);
+    if self.hidden(name) { return Ok(self); }
+
+    let enabled = !self.readonly.contains(name) && !self.group.is_overridden(name);
+    let (group_name, reorder) = (self.group.name, self.group.reorder_affects_layout);
+    let layout = self.group.member_layout(name);
+    let mut degrees = radians.to_degrees();
+    let before = degrees;
+    let dropped = reorder_row(ui, reorder, group_name, name, |ui| {
+      ui.add_enabled_ui(enabled, |ui| {
+        ui.horizontal(|ui| {
+          let knob = ui.add(Knob::new(&mut degrees));
+          ui.add(DragValue::new(&mut degrees).speed(1.0).suffix("°"));
+          ui.label(name);
+          copy_path_menu(&knob, group_name, name, layout);
+        });
+      });
+    });
+
+    if !same_float(degrees, before) {
+      self.group.commit(name, ControlValue::from(degrees.rem_euclid(360.0).to_radians()));
+    }
+    if let Some(dropped) = dropped {
+      self.group.move_control(dropped, name);
+    }
+    Ok(self)
+  }
+
+  /// An int that is always a power of two, e.g. a tile size or atlas dimension. The slider
+  /// steps through the exponents in `exp_range` while the readout shows the value itself.
+  /// Values written any other way, e.g. by `Controls::set`, loading or presets, snap to
+  /// the nearest power of two.
+  pub fn pow2(self, ui: &mut eframe::egui::Ui, name: &'static str, default: u32, exp_range: RangeInclusive<u32>) -> Self {
+    self.try_pow2(ui, name, default, exp_range).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_pow2(mut self, ui: &mut eframe::egui::Ui, name: &'static str, default: u32, exp_range: RangeInclusive<u32>) -> Result<Self, ControlError> {
+    self.declare(name, "an int")?;
+    self.group.pow2.insert(name);
+    let (min, max) = (*exp_range.start().min(&30), *exp_range.end().min(&30));
+    if !self.group.values.inner.contains_key(&name) {
+      let exponent = default.max(1).ilog2().clamp(min, max);
+      self.group.commit(name, ControlValue::from(1i32 << exponent));
+    }
+
+    let value = self.group.values.get_mut(name).unwrap();
+    let mut exponent = match value {
+      ControlValue::Int(v, _, _) => (*v).max(1).ilog2().clamp(min, max) as i32,
+      _ => return Err(ControlError::TypeMismatch { name: name.to_string(), expected: "an int" }),
+    };
+    self.group.ranges.insert(name, vec![(1u32 << min) as f64..=(1u32 << max) as f64]);
+    if self.hidden(name) { return Ok(self); }
+
+    let (modifier, multiplier) = self.speed_modifier;
+    let enabled = !self.readonly.contains(name) && !self.group.is_overridden(name);
+    let tint = self.group.tint_of(name);
+    let (group_name, reorder) = (self.group.name, self.group.reorder_affects_layout);
+    let layout = self.group.member_layout(name);
+    let slider = Slider::from_get_set(min as i32..=max as i32, |v| {
+      if let Some(v) = v {
+        let v = v.clamp(min as i32, max as i32);
+        if v == exponent { return exponent; }
+        exponent = v;
+        self.group.commit(name, ControlValue::from(1i32 << exponent));
+      }
+      return exponent;
+    }).with_title(name).speed_modifier(modifier, multiplier).tint(tint).style(self.slider_style).readout(
+      |e| (1u32 << (e.round() as u32).min(30)).to_string(),
+      |text| text.trim().parse::<u32>().ok().filter(|v| v.is_power_of_two()).map(|v| v.ilog2() as f64),
+    );
+    let dropped = reorder_row(ui, reorder, group_name, name, |ui| { copy_path_menu(&ui.add_enabled(enabled, slider), group_name, name, layout); });
+    if let Some(dropped) = dropped {
+      self.group.move_control(dropped, name);
+    }
+    Ok(self)
+  }
+
+  pub fn float(self, ui: &mut eframe::egui::Ui, name: &'static str, default: f32, r: RangeInclusive<f32>) -> Self {
+    self.try_float(ui, name, default, r).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_float(mut self, ui: &mut eframe::egui::Ui, name: &'static str, default: f32, r: RangeInclusive<f32>) -> Result<Self, ControlError> {
+    self.declare(name, "a float")?;
+    if !self.group.values.inner.contains_key(&name) {
+      self.group.commit(name, ControlValue::from(default));
+    }
+
+    let value = self.group.values.get_mut(name).unwrap();
+    let mut value = match value {
+      ControlValue::Float(v, _, _) => *v,
+      _ => return Err(ControlError::TypeMismatch { name: name.to_string(), expected: "a float" }),
+    };
+    self.group.ranges.insert(name, vec![*r.start() as f64..=*r.end() as f64]);
+    if self.hidden(name) { return Ok(self); }
+
+    let (start, end) = (*r.start() as f64, *r.end() as f64);
+    let (mut selected, bulk) = self.bulk_begin(ui, name);
+    if let Some(delta) = bulk {
+      value = shift_in_range(value as f64, delta, start, end) as f32;
+      self.group.commit(name, ControlValue::from(value));
+    }
+    let before = value;
+
+    let (modifier, multiplier) = self.speed_modifier;
+    let enabled = !self.readonly.contains(name) && !self.group.is_overridden(name);
+    let tint = self.group.tint_of(name);
+    let (group_name, reorder) = (self.group.name, self.group.reorder_affects_layout);
+    let layout = self.group.member_layout(name);
+    let slider = Slider::from_get_set(r, |v| {
+      if let Some(v) = v {
+        if same_float(v, value) { return value; }
+        value = v;
+        self.group.commit(name, ControlValue::from(value));
+      }
+      return value;
+    }).with_title(name).speed_modifier(modifier, multiplier).tint(tint).style(self.slider_style).quantize_input(self.quantize_input).selectable(&mut selected);
+    let dropped = reorder_row(ui, reorder, group_name, name, |ui| { copy_path_menu(&ui.add_enabled(enabled, slider), group_name, name, layout); });
+    if let Some(dropped) = dropped {
+      self.group.move_control(dropped, name);
+    }
+
+    self.bulk_end(ui, name, selected, relative_change(before as f64, value as f64, start, end));
+    Ok(self)
+  }
+
+  pub fn vec2(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 2], r1: RangeInclusive<f32>, r2: RangeInclusive<f32>) -> Self {
+    self.try_vec2(ui, name, default, r1, r2).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_vec2(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 2], r1: RangeInclusive<f32>, r2: RangeInclusive<f32>) -> Result<Self, ControlError> {
+    self.try_vector(ui, name, default, [r1, r2], "a vec2")
+  }
+
+  /// A vec3 with one slider per component, e.g. a camera position.
+  pub fn vec3(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 3], r1: RangeInclusive<f32>, r2: RangeInclusive<f32>, r3: RangeInclusive<f32>) -> Self {
+    self.try_vec3(ui, name, default, r1, r2, r3).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_vec3(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 3], r1: RangeInclusive<f32>, r2: RangeInclusive<f32>, r3: RangeInclusive<f32>) -> Result<Self, ControlError> {
+    self.try_vector(ui, name, default, [r1, r2, r3], "a vec3")
+  }
+
+  /// A vec4 with one slider per component.
+  pub fn vec4(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 4], r1: RangeInclusive<f32>, r2: RangeInclusive<f32>, r3: RangeInclusive<f32>, r4: RangeInclusive<f32>) -> Self {
+    self.try_vec4(ui, name, default, r1, r2, r3, r4).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_vec4(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 4], r1: RangeInclusive<f32>, r2: RangeInclusive<f32>, r3: RangeInclusive<f32>, r4: RangeInclusive<f32>) -> Result<Self, ControlError> {
+    self.try_vector(ui, name, default, [r1, r2, r3, r4], "a vec4")
+  }
+
+  /// Like `vec3` with every component sliding over the same range `r`.
+  pub fn vec3_shared(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 3], r: RangeInclusive<f32>) -> Self {
+    self.try_vec3_shared(ui, name, default, r).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_vec3_shared(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 3], r: RangeInclusive<f32>) -> Result<Self, ControlError> {
+    self.try_vector(ui, name, default, [r.clone(), r.clone(), r], "a vec3")
+  }
+
+  /// Like `vec4` with every component sliding over the same range `r`.
+  pub fn vec4_shared(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 4], r: RangeInclusive<f32>) -> Self {
+    self.try_vec4_shared(ui, name, default, r).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_vec4_shared(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 4], r: RangeInclusive<f32>) -> Result<Self, ControlError> {
+    self.try_vector(ui, name, default, [r.clone(), r.clone(), r.clone(), r], "a vec4")
+  }
+
+  /// Integer vectors, e.g. grid dimensions or work-group sizes, with a slider per component.
+  pub fn ivec2(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [i32; 2], r1: RangeInclusive<i32>, r2: RangeInclusive<i32>) -> Self {
+    self.try_ivec2(ui, name, default, r1, r2).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_ivec2(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [i32; 2], r1: RangeInclusive<i32>, r2: RangeInclusive<i32>) -> Result<Self, ControlError> {
+    self.try_ivector(ui, name, default, [r1, r2], "an ivec2")
+  }
+
+  pub fn ivec3(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [i32; 3], r1: RangeInclusive<i32>, r2: RangeInclusive<i32>, r3: RangeInclusive<i32>) -> Self {
+    self.try_ivec3(ui, name, default, r1, r2, r3).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_ivec3(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [i32; 3], r1: RangeInclusive<i32>, r2: RangeInclusive<i32>, r3: RangeInclusive<i32>) -> Result<Self, ControlError> {
+    self.try_ivector(ui, name, default, [r1, r2, r3], "an ivec3")
+  }
+
+  pub fn ivec4(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [i32; 4], r1: RangeInclusive<i32>, r2: RangeInclusive<i32>, r3: RangeInclusive<i32>, r4: RangeInclusive<i32>) -> Self {
+    self.try_ivec4(ui, name, default, r1, r2, r3, r4).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_ivec4(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [i32; 4], r1: RangeInclusive<i32>, r2: RangeInclusive<i32>, r3: RangeInclusive<i32>, r4: RangeInclusive<i32>) -> Result<Self, ControlError> {
+    self.try_ivector(ui, name, default, [r1, r2, r3, r4], "an ivec4")
+  }
+
+  // `try_vector` for integer components
+  fn try_ivector<const N: usize>(mut self, ui: &mut eframe::egui::Ui, name: &'static str, default: [i32; N], r: [RangeInclusive<i32>; N], expected: &'static str) -> Result<Self, ControlError>
+  where
+    ControlValue: From<[i32; N]>,
+  {
+    self.declare(name, expected)?;
+    if !self.group.values.inner.contains_key(&name) {
+      self.group.commit(name, ControlValue::from(default));
+    };
+
+    let current = self.group.values.get(name).unwrap();
+    if std::mem::discriminant(current) != std::mem::discriminant(&ControlValue::from(default)) {
+      return Err(ControlError::TypeMismatch { name: name.to_string(), expected });
+    }
+    let mut value = default;
+    for (i, c) in current.components().into_iter().enumerate() {
+      value[i] = c as i32;
+    }
+    self.group.ranges.insert(name, r.iter().map(|r| *r.start() as f64..=*r.end() as f64).collect());
+    if self.hidden(name) { return Ok(self); }
+
+    let (mut selected, bulk) = self.bulk_begin(ui, name);
+    if let Some(delta) = bulk {
+      for (c, r) in value.iter_mut().zip(&r) {
+        *c = shift_in_range(*c as f64, delta, *r.start() as f64, *r.end() as f64).round() as i32;
+      }
+      self.group.commit(name, ControlValue::from(value));
+    }
+    let before = value;
+
+    let (modifier, multiplier) = self.speed_modifier;
+    let enabled = !self.readonly.contains(name) && !self.group.is_overridden(name);
+    let tint = self.group.tint_of(name);
+    let (group_name, reorder) = (self.group.name, self.group.reorder_affects_layout);
+    let layout = self.group.member_layout(name);
+    for (i, range) in r.iter().enumerate() {
+      let title = format!("{}.{}", name, COMPONENT_NAMES[i]);
+      let slider = Slider::from_get_set(range.clone(), |v| {
+        if let Some(v) = v {
+          if v == value[i] { return value[i]; }
+          value[i] = v;
+          self.group.commit(name, ControlValue::from(value));
+        }
+        return value[i];
+      }).with_title(title.as_str()).speed_modifier(modifier, multiplier).tint(tint).style(self.slider_style).selectable(&mut selected);
+      if i > 0 {
+        ui.add_enabled(enabled, slider);
+        continue;
+      }
+      let dropped = reorder_row(ui, reorder, group_name, name, |ui| { copy_path_menu(&ui.add_enabled(enabled, slider), group_name, name, layout); });
+      if let Some(dropped) = dropped {
+        self.group.move_control(dropped, name);
+      }
+    }
+
+    let changed = (0..N).find(|i| value[*i] != before[*i]);
+    let delta = changed.map_or(0.0, |i| relative_change(before[i] as f64, value[i] as f64, *r[i].start() as f64, *r[i].end() as f64));
+    self.bulk_end(ui, name, selected, delta);
+    Ok(self)
+  }
+
+  // one slider per component, with a toggle on the first row that links them so dragging
+  // any one sets them all
+  fn try_vector<const N: usize>(mut self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; N], r: [RangeInclusive<f32>; N], expected: &'static str) -> Result<Self, ControlError>
+  where
+    ControlValue: From<[f32; N]>,
+  {
+    self.declare(name, expected)?;
+    if !self.group.values.inner.contains_key(&name) {
+      self.group.commit(name, ControlValue::from(default));
+    };
+
+    let current = self.group.values.get(name).unwrap();
+    if std::mem::discriminant(current) != std::mem::discriminant(&ControlValue::from(default)) {
+      return Err(ControlError::TypeMismatch { name: name.to_string(), expected });
+    }
+    let mut value = default;
+    for (i, c) in current.components().into_iter().enumerate() {
+      value[i] = c as f32;
+    }
+    self.group.ranges.insert(name, r.iter().map(|r| *r.start() as f64..=*r.end() as f64).collect());
+    if self.hidden(name) { return Ok(self); }
+
+    let (mut selected, bulk) = self.bulk_begin(ui, name);
+    if let Some(delta) = bulk {
+      for (c, r) in value.iter_mut().zip(&r) {
+        *c = shift_in_range(*c as f64, delta, *r.start() as f64, *r.end() as f64) as f32;
+      }
+      self.group.commit(name, ControlValue::from(value));
+    }
+    let before = value;
+
+    let (modifier, multiplier) = self.speed_modifier;
+    let enabled = !self.readonly.contains(name) && !self.group.is_overridden(name);
+    let tint = self.group.tint_of(name);
+    let (group_name, reorder) = (self.group.name, self.group.reorder_affects_layout);
+    let layout = self.group.member_layout(name);
+    let mut linked = self.group.linked.contains(name);
+    let link = linked;
+    for (i, range) in r.iter().enumerate() {
+      let title = format!("{}.{}", name, COMPONENT_NAMES[i]);
+      let slider = Slider::from_get_set(range.clone(), |v| {
+        if let Some(v) = v {
+          if same_float(v, value[i]) { return value[i]; }
+          if link { value = [v; N] } else { value[i] = v }
+          self.group.commit(name, ControlValue::from(value));
+        }
+        return value[i];
+      }).with_title(title.as_str()).speed_modifier(modifier, multiplier).tint(tint).style(self.slider_style).quantize_input(self.quantize_input).selectable(&mut selected);
+      if i > 0 {
+        ui.add_enabled(enabled, slider);
+        continue;
+      }
+      let dropped = reorder_row(ui, reorder, group_name, name, |ui| {
+        ui.toggle_value(&mut linked, "🔗").on_hover_text("link components");
+        copy_path_menu(&ui.add_enabled(enabled, slider), group_name, name, layout);
+      });
+      if let Some(dropped) = dropped {
+        self.group.move_control(dropped, name);
+      }
+    }
+
+    if linked != link {
+      if linked { self.group.linked.insert(name); } else { self.group.linked.remove(name); }
+    }
+
+    let changed = (0..N).find(|i| !same_float(value[*i], before[*i]));
+    let delta = changed.map_or(0.0, |i| relative_change(before[i] as f64, value[i] as f64, *r[i].start() as f64, *r[i].end() as f64));
+    self.bulk_end(ui, name, selected, delta);
+    Ok(self)
+  }
+
+  /// An RGB color picker, stored as a vec3 of linear components in 0..=1. It packs like a
+  /// `vec3` slider, so it can replace one without touching the shader.
+  pub fn color_rgb(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 3]) -> Self {
+    self.try_color_rgb(ui, name, default).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_color_rgb(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 3]) -> Result<Self, ControlError> {
+    self.try_color_edit(ui, name, default, "a vec3", |ui, color| ui.color_edit_button_rgb(color))
+  }
+
+  /// Like `color_rgb` with an unpremultiplied alpha, stored as a vec4.
+  pub fn color_rgba(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 4]) -> Self {
+    self.try_color_rgba(ui, name, default).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_color_rgba(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 4]) -> Result<Self, ControlError> {
+    self.try_color_edit(ui, name, default, "a vec4", |ui, color| ui.color_edit_button_rgba_unmultiplied(color))
+  }
+
+  /// An RGBA color picker, the same as `color_rgba`. Packs like a `vec4` uniform.
+  pub fn color(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 4]) -> Self {
+    self.color_rgba(ui, name, default)
+  }
+
+  pub fn try_color(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 4]) -> Result<Self, ControlError> {
+    self.try_color_rgba(ui, name, default)
+  }
+
+  fn try_color_edit<const N: usize>(mut self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; N], expected: &'static str, edit: impl FnOnce(&mut eframe::egui::Ui, &mut [f32; N]) -> eframe::egui::Response) -> Result<Self, ControlError>
+  where
+    ControlValue: From<[f32; N]>,
+  {
+    self.declare(name, expected)?;
+    if !self.group.values.inner.contains_key(&name) {
+      self.group.commit(name, ControlValue::from(default));
+    };
+
+    let current = self.group.values.get(name).unwrap();
+    if std::mem::discriminant(current) != std::mem::discriminant(&ControlValue::from(default)) {
+      return Err(ControlError::TypeMismatch { name: name.to_string(), expected });
+    }
+    let mut value = default;
+    for (i, c) in current.components().into_iter().enumerate() {
+      value[i] = c as f32;
+    }
+    self.group.ranges.insert(name, vec![0.0..=1.0; N]);
+    if self.hidden(name) { return Ok(self); }
+
+    let enabled = !self.readonly.contains(name) && !self.group.is_overridden(name);
+    let (group_name, reorder) = (self.group.name, self.group.reorder_affects_layout);
+    let layout = self.group.member_layout(name);
+    let before = value;
+    let dropped = reorder_row(ui, reorder, group_name, name, |ui| {
+      ui.add_enabled_ui(enabled, |ui| {
+        ui.horizontal(|ui| {
+          ui.label(name);
+          copy_path_menu(&edit(ui, &mut value), group_name, name, layout);
+        });
+      });
+    });
+
+    if !value.iter().zip(&before).all(|(a, b)| same_float(*a, *b)) {
+      self.group.commit(name, ControlValue::from(value));
+    }
+    if let Some(dropped) = dropped {
+      self.group.move_control(dropped, name);
+    }
+    Ok(self)
+  }
+
+  /// A color gradient edited as stops, see `GradientEditor`, packed as a lookup table of
+  /// `GRADIENT_LUT_SIZE` vec4s for a uniform array; upload the same bytes for a 1D texture.
+  pub fn gradient(self, ui: &mut eframe::egui::Ui, name: &'static str, default: &[(f32, [f32; 4])]) -> Self {
+    self.try_gradient(ui, name, default).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_gradient(mut self, ui: &mut eframe::egui::Ui, name: &'static str, default: &[(f32, [f32; 4])]) -> Result<Self, ControlError> {
+    self.declare(name, "a gradient")?;
+    if !self.group.values.inner.contains_key(&name) {
+      self.group.commit(name, ControlValue::from(default.to_vec()));
+    };
+
+    let value = self.group.values.get_mut(name).unwrap();
+    let mut stops = match value {
+      ControlValue::Gradient(stops, _, _) => stops.clone(),
+      _ => return Err(ControlError::TypeMismatch { name: name.to_string(), expected: "a gradient" }),
+    };
+    if self.hidden(name) { return Ok(self); }
+
+    let enabled = !self.readonly.contains(name) && !self.group.is_overridden(name);
+    let (group_name, reorder) = (self.group.name, self.group.reorder_affects_layout);
+    let layout = self.group.member_layout(name);
+    let mut changed = false;
+    let dropped = reorder_row(ui, reorder, group_name, name, |ui| {
+      ui.label(name);
+      let response = ui.add_enabled(enabled, GradientEditor::new((group_name, name), &mut stops));
+      changed = response.changed();
+      copy_path_menu(&response, group_name, name, layout);
+    });
+    if changed {
+      self.group.commit(name, ControlValue::from(stops));
+    }
+    if let Some(dropped) = dropped {
+      self.group.move_control(dropped, name);
+    }
+    Ok(self)
+  }
+
+  /// A curve from 0 to 1 on both axes, e.g. a falloff or remap function, edited by its
+  /// points and packed as `resolution` evenly spaced samples four to a vec4, a std140
+  /// `vec4[resolution / 4]`. Sample `i` is `lut[i / 4][i % 4]` in the shader. Panics unless
+  /// `resolution` is a multiple of 4 up to `curve::CURVE_MAX_RESOLUTION`.
+  pub fn curve(self, ui: &mut eframe::egui::Ui, name: &'static str, default: &[[f32; 2]], resolution: usize) -> Self {
+    self.try_curve(ui, name, default, resolution).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_curve(mut self, ui: &mut eframe::egui::Ui, name: &'static str, default: &[[f32; 2]], resolution: usize) -> Result<Self, ControlError> {
+    if curve::check_resolution(resolution).is_err() {
+      return Err(ControlError::InvalidResolution { name: name.to_string(), resolution });
+    }
+    self.declare(name, "a curve")?;
+    if !self.group.values.inner.contains_key(&name) {
+      self.group.commit(name, ControlValue::from((default.to_vec(), resolution)));
+    };
+
+    let value = self.group.values.get_mut(name).unwrap();
+    let (mut points, packed) = match value {
+      ControlValue::Curve(points, bytes, _) => (points.clone(), bytes.len() / 4),
+      _ => return Err(ControlError::TypeMismatch { name: name.to_string(), expected: "a curve" }),
+    };
+    self.group.ranges.insert(name, vec![0.0..=1.0; points.len() * 2]);
+    // loaded values keep the resolution they were saved with until resampled here
+    if packed != resolution {
+      self.group.commit(name, ControlValue::from((points.clone(), resolution)));
+    }
+    if self.hidden(name) { return Ok(self); }
+
+    let enabled = !self.readonly.contains(name) && !self.group.is_overridden(name);
+    let (group_name, reorder) = (self.group.name, self.group.reorder_affects_layout);
+    let layout = self.group.member_layout(name);
+    let mut changed = false;
+    let dropped = reorder_row(ui, reorder, group_name, name, |ui| {
+      ui.label(name);
+      let response = ui.add_enabled(enabled, CurveEditor::new((group_name, name), &mut points));
+      changed = response.changed();
+      copy_path_menu(&response, group_name, name, layout);
+    });
+    if changed {
+      self.group.commit(name, ControlValue::from((points, resolution)));
+    }
+    if let Some(dropped) = dropped {
+      self.group.move_control(dropped, name);
+    }
+    Ok(self)
+  }
+
+  /// A vec2 set by pointing on a 2D pad instead of two sliders, e.g. a light position or a
+  /// UV offset. x grows rightwards over `x_range` and y upwards over `y_range`.
+  pub fn vec2_pad(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 2], x_range: RangeInclusive<f32>, y_range: RangeInclusive<f32>) -> Self {
+    self.try_vec2_pad(ui, name, default, x_range, y_range).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_vec2_pad(mut self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 2], x_range: RangeInclusive<f32>, y_range: RangeInclusive<f32>) -> Result<Self, ControlError> {
+    self.declare(name, "a vec2")?;
+    if !self.group.values.inner.contains_key(&name) {
+      self.group.commit(name, ControlValue::from(default));
+    };
+
+    let value = self.group.values.get_mut(name).unwrap();
+    let mut value = match value {
+      ControlValue::Vec2(v, _, _) => *v,
+      _ => return Err(ControlError::TypeMismatch { name: name.to_string(), expected: "a vec2" }),
+    };
+    self.group.ranges.insert(name, vec![*x_range.start() as f64..=*x_range.end() as f64, *y_range.start() as f64..=*y_range.end() as f64]);
+    if self.hidden(name) { return Ok(self); }
+
+    let enabled = !self.readonly.contains(name) && !self.group.is_overridden(name);
+    let (group_name, reorder) = (self.group.name, self.group.reorder_affects_layout);
+    let layout = self.group.member_layout(name);
+    let before = value;
+    let dropped = reorder_row(ui, reorder, group_name, name, |ui| {
+      ui.vertical(|ui| {
+        ui.label(format!("{} ({:.3}, {:.3})", name, value[0], value[1]));
+        let response = ui.add_enabled(enabled, Pad::new(&mut value, x_range, y_range));
+        copy_path_menu(&response, group_name, name, layout);
+      });
+    });
+
+    if !value.iter().zip(&before).all(|(a, b)| same_float(*a, *b)) {
+      self.group.commit(name, ControlValue::from(value));
+    }
+    if let Some(dropped) = dropped {
+      self.group.move_control(dropped, name);
+    }
+    Ok(self)
+  }
+
+  /// A unit vec3 turned on an arcball gizmo, e.g. a light direction. The default and any
+  /// loaded value are normalized.
+  pub fn direction(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 3]) -> Self {
+    self.try_direction(ui, name, default).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_direction(mut self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 3]) -> Result<Self, ControlError> {
+    self.declare(name, "a vec3")?;
+    if !self.group.values.inner.contains_key(&name) {
+      self.group.commit(name, ControlValue::from(gizmo::normalize(default)));
+    };
+
+    let value = self.group.values.get_mut(name).unwrap();
+    let stored = match value {
+      ControlValue::Vec3(v, _, _) => *v,
+      _ => return Err(ControlError::TypeMismatch { name: name.to_string(), expected: "a vec3" }),
+    };
+    self.group.ranges.insert(name, vec![-1.0..=1.0; 3]);
+    // renormalize only when off by more than rounding, so a unit value isn't recommitted every frame
+    let mut value = stored;
+    if (stored.iter().map(|c| c * c).sum::<f32>() - 1.0).abs() > 1e-4 {
+      value = gizmo::normalize(stored);
+      self.group.commit(name, ControlValue::from(value));
+    }
+    if self.hidden(name) { return Ok(self); }
+
+    let enabled = !self.readonly.contains(name) && !self.group.is_overridden(name);
+    let (group_name, reorder) = (self.group.name, self.group.reorder_affects_layout);
+    let layout = self.group.member_layout(name);
+    let before = value;
+    let dropped = reorder_row(ui, reorder, group_name, name, |ui| {
+      ui.horizontal(|ui| {
+        let response = ui.add_enabled(enabled, DirectionGizmo::new(&mut value));
+        ui.label(format!("{}\n({:.3}, {:.3}, {:.3})", name, value[0], value[1], value[2]));
+        copy_path_menu(&response, group_name, name, layout);
+      });
+    });
+
+    if !value.iter().zip(&before).all(|(a, b)| same_float(*a, *b)) {
+      self.group.commit(name, ControlValue::from(value));
+    }
+    if let Some(dropped) = dropped {
+      self.group.move_control(dropped, name);
+    }
+    Ok(self)
+  }
+
+  /// A 2D transform, e.g. for UVs, edited as translation, rotation in degrees and scale,
+  /// and stored as a homogeneous mat3.
+  pub fn transform_2d(self, ui: &mut eframe::egui::Ui, name: &'static str, default: Trs2) -> Self {
+    self.try_transform_2d(ui, name, default).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_transform_2d(mut self, ui: &mut eframe::egui::Ui, name: &'static str, default: Trs2) -> Result<Self, ControlError> {
+    self.declare(name, "a mat3")?;
+    if !self.group.values.inner.contains_key(&name) {
+      self.group.commit(name, ControlValue::from(default.to_mat3()));
+    };
+
+    let value = self.group.values.get_mut(name).unwrap();
+    let trs = match value {
+      ControlValue::Mat3(m, _, _) => Trs2::from_mat3(m),
+      _ => return Err(ControlError::TypeMismatch { name: name.to_string(), expected: "a mat3" }),
+    };
+    if self.hidden(name) { return Ok(self); }
+
+    let enabled = !self.readonly.contains(name) && !self.group.is_overridden(name);
+    let (group_name, reorder) = (self.group.name, self.group.reorder_affects_layout);
+    let layout = self.group.member_layout(name);
+    let mut edited = trs;
+    let dropped = reorder_row(ui, reorder, group_name, name, |ui| {
+      let response = ui.label(name);
+      copy_path_menu(&response, group_name, name, layout);
+      ui.add_enabled_ui(enabled, |ui| {
+        Grid::new((group_name, name)).num_columns(2).show(ui, |ui| {
+          ui.label("translate");
+          ui.horizontal(|ui| edited.translation.iter_mut().for_each(|c| { ui.add(DragValue::new(c).speed(0.01)); }));
+          ui.end_row();
+          ui.label("rotate");
+          ui.add(DragValue::new(&mut edited.rotation).speed(1.0).suffix("°"));
+          ui.end_row();
+          ui.label("scale");
+          ui.horizontal(|ui| edited.scale.iter_mut().for_each(|c| { ui.add(DragValue::new(c).speed(0.01)); }));
+          ui.end_row();
+        });
+      });
+    });
+
+    // recomposed only on edits, so decomposing doesn't drift the stored matrix
+    let unchanged = edited.translation.iter().chain(&edited.scale).chain([&edited.rotation])
+      .zip(trs.translation.iter().chain(&trs.scale).chain([&trs.rotation]))
+      .all(|(a, b)| same_float(*a, *b));
+    if !unchanged {
+      self.group.commit(name, ControlValue::from(edited.to_mat3()));
+    }
+    if let Some(dropped) = dropped {
+      self.group.move_control(dropped, name);
+    }
+    Ok(self)
+  }
+
+  /// A 3D transform edited as translation, XYZ euler rotation in degrees and scale, and
+  /// stored as a mat4.
+  pub fn transform_3d(self, ui: &mut eframe::egui::Ui, name: &'static str, default: Trs3) -> Self {
+    self.try_transform_3d(ui, name, default).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_transform_3d(mut self, ui: &mut eframe::egui::Ui, name: &'static str, default: Trs3) -> Result<Self, ControlError> {
+    self.declare(name, "a mat4")?;
+    if !self.group.values.inner.contains_key(&name) {
+      self.group.commit(name, ControlValue::from(default.to_mat4()));
+    };
+
+    let value = self.group.values.get_mut(name).unwrap();
+    let trs = match value {
+      ControlValue::Mat4(m, _, _) => Trs3::from_mat4(m),
+      _ => return Err(ControlError::TypeMismatch { name: name.to_string(), expected: "a mat4" }),
+    };
+    if self.hidden(name) { return Ok(self); }
+
+    let enabled = !self.readonly.contains(name) && !self.group.is_overridden(name);
+    let (group_name, reorder) = (self.group.name, self.group.reorder_affects_layout);
+    let layout = self.group.member_layout(name);
+    let mut edited = trs;
+    let dropped = reorder_row(ui, reorder, group_name, name, |ui| {
+      let response = ui.label(name);
+      copy_path_menu(&response, group_name, name, layout);
+      ui.add_enabled_ui(enabled, |ui| {
+        Grid::new((group_name, name)).num_columns(2).show(ui, |ui| {
+          ui.label("translate");
+          ui.horizontal(|ui| edited.translation.iter_mut().for_each(|c| { ui.add(DragValue::new(c).speed(0.01)); }));
+          ui.end_row();
+          ui.label("rotate");
+          ui.horizontal(|ui| edited.rotation.iter_mut().for_each(|c| { ui.add(DragValue::new(c).speed(1.0).suffix("°")); }));
+          ui.end_row();
+          ui.label("scale");
+          ui.horizontal(|ui| edited.scale.iter_mut().for_each(|c| { ui.add(DragValue::new(c).speed(0.01)); }));
+          ui.end_row();
+        });
+      });
+    });
+
+    let unchanged = edited.translation.iter().chain(&edited.rotation).chain(&edited.scale)
+      .zip(trs.translation.iter().chain(&trs.rotation).chain(&trs.scale))
+      .all(|(a, b)| same_float(*a, *b));
+    if !unchanged {
+      self.group.commit(name, ControlValue::from(edited.to_mat4()));
+    }
+    if let Some(dropped) = dropped {
+      self.group.move_control(dropped, name);
+    }
+    Ok(self)
+  }
+
+  /// A read-only view of the control `source`, possibly in another group, drawn as a
+  /// disabled slider over its declared range. Stores nothing and draws nothing while
+  /// `source` doesn't exist.
+  pub fn mirror(self, ui: &mut eframe::egui::Ui, name: &'static str, source: ControlRef) -> Self {
+    if self.hidden(name) { return self; }
+    let group = match source.group == self.group.name {
+      true => Some(&*self.group),
+      false => self.others.and_then(|others| others.get(source.group)),
+    };
+    let Some(group) = group else { return self };
+    let Some(value) = group.values.get(source.name) else { return self };
+
+    let components = value.components();
+    let ranges = group.ranges.get(source.name).cloned().unwrap_or_default();
+    for (i, c) in components.iter().enumerate() {
+      let range = ranges.get(i).cloned().unwrap_or(0.0..=1.0);
+      let title = if components.len() > 1 { format!("{}.{}", name, component_name(i, components.len())) } else { name.to_string() };
+      ui.add_enabled(false, Slider::from_get_set(range, |_| *c).with_title(title.as_str()).tint(group.tint_of(source.name)).style(self.slider_style));
+    }
+    self
+  }
+
+  /// Adds a hex text field for the already declared control `name`: `#RRGGBB` / `#RRGGBBAA`
+  /// for vec3 / vec4 colors and `0x...` for ints. Other types are ignored.
+  pub fn hex(self, ui: &mut eframe::egui::Ui, name: &'static str) -> Self {
+    if self.hidden(name) || self.readonly.contains(name) { return self; }
+    let Some(value) = self.group.values.get(name) else { return self };
+    let id = ui.id().with(self.group.name).with(name).with("hex");
+    if let Some(updated) = hex::edit(ui, id, value) {
+      self.group.commit(name, updated);
+    }
+    self
+  }
+
+  /// A vec2 laid out on one row as small drag values, without slider tracks.
+  pub fn vec2_inline(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 2], r: RangeInclusive<f32>) -> Self {
+    self.try_vec2_inline(ui, name, default, r).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_vec2_inline(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 2], r: RangeInclusive<f32>) -> Result<Self, ControlError> {
+    self.try_vector_inline(ui, name, default, r, "a vec2")
+  }
+
+  /// A vec3 laid out on one row as small drag values, without slider tracks.
+  pub fn vec3_inline(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 3], r: RangeInclusive<f32>) -> Self {
+    self.try_vec3_inline(ui, name, default, r).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_vec3_inline(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 3], r: RangeInclusive<f32>) -> Result<Self, ControlError> {
+    self.try_vector_inline(ui, name, default, r, "a vec3")
+  }
+
+  fn try_vector_inline<const N: usize>(mut self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; N], r: RangeInclusive<f32>, expected: &'static str) -> Result<Self, ControlError>
+  where
+    ControlValue: From<[f32; N]>,
+  {
+    self.declare(name, expected)?;
+    if !self.group.values.inner.contains_key(&name) {
+      self.group.commit(name, ControlValue::from(default));
+    };
+
+    let current = self.group.values.get(name).unwrap();
+    if std::mem::discriminant(current) != std::mem::discriminant(&ControlValue::from(default)) {
+      return Err(ControlError::TypeMismatch { name: name.to_string(), expected });
+    }
+    let mut value = default;
+    for (i, c) in current.components().into_iter().enumerate() {
+      value[i] = c as f32;
+    }
+    self.group.ranges.insert(name, vec![*r.start() as f64..=*r.end() as f64; N]);
+    if self.hidden(name) { return Ok(self); }
+
+    let (start, end) = (*r.start() as f64, *r.end() as f64);
+    let (mut selected, bulk) = self.bulk_begin(ui, name);
+    if let Some(delta) = bulk {
+      for c in value.iter_mut() {
+        *c = shift_in_range(*c as f64, delta, start, end) as f32;
+      }
+      self.group.commit(name, ControlValue::from(value));
+    }
+    let before = value;
+
+    let (modifier, multiplier) = self.speed_modifier;
+    let speed = if ui.input(|i| i.modifiers.contains(modifier)) { 0.1 * multiplier } else { 0.1 };
+    let enabled = !self.readonly.contains(name) && !self.group.is_overridden(name);
+    let style = self.slider_style;
+    let (group_name, reorder) = (self.group.name, self.group.reorder_affects_layout);
+    let layout = self.group.member_layout(name);
+    let dropped = reorder_row(ui, reorder, group_name, name, |ui| {
+      ui.add_enabled_ui(enabled, |ui| {
+        // shift-clicking the name selects the control, like a slider's title
+        let label = match style.label_column {
+          true => ui.add_sized(vec2(style.title_width, ui.spacing().interact_size.y), Label::new(name).truncate().sense(Sense::click())),
+          false => ui.add(Label::new(name).sense(Sense::click())),
+        };
+        if label.clicked() && ui.input(|i| i.modifiers.shift) {
+          selected = !selected;
+        }
+        if selected {
+          ui.painter().rect_stroke(label.rect, style.corner_radius, Stroke::new(1.0, ui.visuals().selection.stroke.color));
+        }
+        copy_path_menu(&label, group_name, name, layout);
+        for c in value.iter_mut() {
+          ui.add(DragValue::new(c).speed(speed).range(r.clone()));
+        }
+      });
+    });
+    if let Some(dropped) = dropped {
+      self.group.move_control(dropped, name);
+    }
+
+    let changed = (0..N).find(|i| !same_float(value[*i], before[*i]));
+    if changed.is_some() {
+      self.group.commit(name, ControlValue::from(value));
+    }
+    let delta = changed.map_or(0.0, |i| relative_change(before[i] as f64, value[i] as f64, start, end));
+    self.bulk_end(ui, name, selected, delta);
+    Ok(self)
+  }
+
+  /// A vec3 whose components always satisfy `x <= y <= z`, e.g. thresholds or LOD bands.
+  pub fn vec3_sorted(self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 3], r: RangeInclusive<f32>) -> Self {
+    self.try_vec3_sorted(ui, name, default, r).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_vec3_sorted(mut self, ui: &mut eframe::egui::Ui, name: &'static str, default: [f32; 3], r: RangeInclusive<f32>) -> Result<Self, ControlError> {
+    self.declare(name, "a vec3")?;
+    if !self.group.values.inner.contains_key(&name) {
+      self.group.commit(name, ControlValue::from(default));
+    };
+
+    let value = self.group.values.get_mut(name).unwrap();
+    let mut value = match value {
+      ControlValue::Vec3(v, _, _) => *v,
+      _ => return Err(ControlError::TypeMismatch { name: name.to_string(), expected: "a vec3" }),
+    };
+    self.group.ranges.insert(name, vec![*r.start() as f64..=*r.end() as f64; 3]);
+    if self.hidden(name) { return Ok(self); }
+
+    let (start, end) = (*r.start() as f64, *r.end() as f64);
+    let (mut selected, bulk) = self.bulk_begin(ui, name);
+    if let Some(delta) = bulk {
+      // every component moves by the same amount, so they stay in order
+      for c in value.iter_mut() {
+        *c = shift_in_range(*c as f64, delta, start, end) as f32;
+      }
+      self.group.commit(name, ControlValue::from(value));
+    }
+    let before = value;
+
+    let (modifier, multiplier) = self.speed_modifier;
+    let enabled = !self.readonly.contains(name) && !self.group.is_overridden(name);
+    let tint = self.group.tint_of(name);
+    let mode = self.sort_mode;
+    let (group_name, reorder) = (self.group.name, self.group.reorder_affects_layout);
+    let layout = self.group.member_layout(name);
+    let mut dragged = None;
+    for i in 0..3 {
+      let title = format!("{}.{}", name, COMPONENT_NAMES[i]);
+      let slider = Slider::from_get_set(r.clone(), |v| {
+        if let Some(v) = v {
+          if same_float(v, value[i]) { return value[i]; }
+          set_sorted(&mut value, i, v, mode);
+          dragged = Some(i);
+          self.group.commit(name, ControlValue::from(value));
+        }
+        return value[i];
+      }).with_title(title.as_str()).speed_modifier(modifier, multiplier).tint(tint).style(self.slider_style).selectable(&mut selected);
+      if i > 0 {
+        ui.add_enabled(enabled, slider);
+        continue;
+      }
+      let dropped = reorder_row(ui, reorder, group_name, name, |ui| { copy_path_menu(&ui.add_enabled(enabled, slider), group_name, name, layout); });
+      if let Some(dropped) = dropped {
+        self.group.move_control(dropped, name);
+      }
+    }
+
+    // measured on the dragged component, not one pushed along to keep the order
+    let delta = dragged.map_or(0.0, |i| relative_change(before[i] as f64, value[i] as f64, start, end));
+    self.bulk_end(ui, name, selected, delta);
+    Ok(self)
+  }
+
+  // whether `name` is selected, and the relative delta another selected control was dragged by last frame
+  fn bulk_begin(&mut self, ui: &eframe::egui::Ui, name: &'static str) -> (bool, Option<f64>) {
+    let key = (self.group.name, name);
+    let Some(selection) = self.selection.as_deref_mut() else { return (false, None) };
+    selection.sync(ui.ctx().frame_nr());
+    let selected = selection.controls.contains(&key);
+    let delta = match selection.pending {
+      Some((source, delta)) if selected && source != key => Some(delta),
+      _ => None,
+    };
+    (selected, delta)
+  }
+
+  fn bulk_end(&mut self, ui: &eframe::egui::Ui, name: &'static str, selected: bool, delta: f64) {
+    let key = (self.group.name, name);
+    let Some(selection) = self.selection.as_deref_mut() else { return };
+    if selected {
+      selection.controls.insert(key);
+    } else {
+      selection.controls.remove(&key);
+    }
+
+    if selected && delta != 0.0 && delta.is_finite() {
+      selection.current = Some((key, delta));
+      ui.ctx().request_repaint();
+    }
+  }
+
+  pub fn button(self, ui: &mut eframe::egui::Ui, title: &'static str, mut action: impl FnMut()) -> Self {
+      if self.visible && ui.button(title).clicked() {
+        action();
+      }
+      self
+    }
+
+}
+
+// -------------------- action group -------------------- //
+/// One entry of an action row, drawn in the order it was added.
+pub enum ActionItem {
+  Button(&'static str, Box<dyn FnMut(&mut Controls)>),
+  Separator,
+  Label(&'static str),
+}
+
+pub struct ActionGroup {
+  pub name: &'static str,
+  /// Buttons and layout items in the order the builder added them, which is the order
+  /// they are drawn in and the order shortcuts are checked in.
+  pub actions: Vec<ActionItem>,
+  pub shortcuts: BTreeMap<&'static str, KeyboardShortcut>,
+}
+
+impl ActionGroup {
+  pub fn new(name: &'static str) -> ActionGroup {
+    ActionGroup {
+      name,
+      actions: Vec::new(),
+      shortcuts: BTreeMap::new(),
+    }
+  }
+}
+
+
+pub struct ActionGroupBuilder<'a> {
+  group: &'a mut ActionGroup,
+}
+
+impl<'a> ActionGroupBuilder<'a> {
+  pub fn new(group: &'a mut ActionGroup) -> ActionGroupBuilder<'a> {
+    ActionGroupBuilder {
+      group,
+    }
+  }
+
+  /// Adds a button, or replaces the action of the button already named `name`.
+  pub fn button(self, name: &'static str, action: impl FnMut(&mut Controls) + 'static) -> Self {
+    let existing = self.group.actions.iter_mut().find(|item| matches!(item, ActionItem::Button(n, _) if *n == name));
+    match existing {
+      Some(ActionItem::Button(_, current)) => *current = Box::new(action),
+      _ => self.group.actions.push(ActionItem::Button(name, Box::new(action))),
+    }
+    self
+  }
+
+  /// A vertical line between the buttons before and after it.
+  pub fn separator(self) -> Self {
+    self.group.actions.push(ActionItem::Separator);
+    self
+  }
+
+  /// A caption for the buttons that follow, e.g. "File:".
+  pub fn label(self, text: &'static str) -> Self {
+    self.group.actions.push(ActionItem::Label(text));
+    self
+  }
+
+  /// Fires the action `name` when `shortcut` is pressed, and shows it on the button. A
+  /// shortcut is consumed by the first action that checks it, so when two actions share
+  /// one, the action group drawn first (and within it, the button listed first) wins.
+  pub fn shortcut(self, name: &'static str, shortcut: KeyboardShortcut) -> Self {
+    self.group.shortcuts.insert(name, shortcut);
+    self
+  }
+}
+// -------------------- reordering -------------------- //
+#[derive(Clone, Copy)]
+struct ReorderPayload {
+  group: &'static str,
+  name: &'static str,
+}
+
+// lays `add_contents` out in a row, behind a drag handle when `on`, and returns the control
+// of the same group that was dropped onto the row
+fn reorder_row(ui: &mut eframe::egui::Ui, on: bool, group: &'static str, name: &'static str, add_contents: impl FnOnce(&mut eframe::egui::Ui)) -> Option<&'static str> {
+  let row = ui.horizontal(|ui| {
+    if on {
+      let id = ui.id().with((group, name, "reorder"));
+      ui.dnd_drag_source(id, ReorderPayload { group, name }, |ui| ui.label("☰"));
+    }
+    add_contents(ui);
+  });
+
+  if !on { return None; }
+  let dropped = row.response.dnd_release_payload::<ReorderPayload>()?;
+  (dropped.group == group && dropped.name != name).then_some(dropped.name)
+}
+
+// right-click menu copying `group.name`, or with its byte range in the packed buffer
+fn copy_path_menu(response: &eframe::egui::Response, group: &'static str, name: &'static str, layout: Option<(usize, usize)>) {
+  response.context_menu(|ui| {
+    if ui.button("Copy path").clicked() {
+      ui.ctx().copy_text(format!("{}.{}", group, name));
+      ui.close_menu();
+    }
+    if let Some((offset, size)) = layout {
+      if ui.button(format!("Copy path with offset ({})", offset)).clicked() {
+        ui.ctx().copy_text(format!("{}.{} @ {}..{}", group, name, offset, offset + size));
+        ui.close_menu();
+      }
+    }
+  });
+}
+
+// -------------------- callbacks -------------------- //
+struct Debounced {
+  path: ControlPath,
+  delay: f64,
+  callback: Box<dyn FnMut(&ControlValue)>,
+  last_seen: Option<ControlValue>,
+  changed_at: Option<f64>,
+}
+
+impl std::fmt::Debug for Debounced {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("Debounced").field("path", &self.path).field("delay", &self.delay).finish()
+  }
+}
+
+struct Uploader(Box<dyn FnMut(&[u8])>);
+
+impl std::fmt::Debug for Uploader {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str("Uploader")
+  }
+}
+
+// -------------------- tab badge -------------------- //
+fn tab_badge(ui: &mut eframe::egui::Ui, modified: usize, dirty: bool) {
+  let text = match (modified, dirty) {
+    (0, false) => return,
+    (0, true) => "dirty".to_string(),
+    (n, false) => format!("{} modified", n),
+    (n, true) => format!("{} modified / dirty", n),
+  };
+  ui.label(RichText::new(text).small().weak());
+}
+
+// -------------------- inspector -------------------- //
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum InspectorSort {
+  #[default]
+  Group,
+  Name,
+  Type,
+}
+
+// an inline editor for any value, returning the edited value when it changed
+fn edit_value(ui: &mut eframe::egui::Ui, value: &ControlValue) -> Option<ControlValue> {
+  if let ControlValue::Bool(v, _, _) = value {
+    let mut checked = *v != 0;
+    return ui.checkbox(&mut checked, "").changed().then(|| ControlValue::from(checked));
+  }
+
+  ui.horizontal(|ui| {
+    let mut updated = None;
+    for (i, mut c) in value.components().into_iter().enumerate() {
+      if ui.add(DragValue::new(&mut c).speed(0.01)).changed() {
+        updated = value.with_component(i, c);
+      }
+    }
+    updated
+  }).inner
+}
+
+// -------------------- controls -------------------- //
+/// Declares a whole panel at once, groups and controls in order:
+/// ```ignore
+/// let controls = controls! {
+///   "lighting": {
+///     "intensity": { default: 0.5, min: 0.0, max: 1.0 },
+///     "dir": { default: [0.0, 1.0, 0.0] },
+///   },
+/// };
+/// ```
+/// The type of `default` picks the control's type: an integer literal makes an int, a
+/// float literal a float and an array of 2 to 4 floats a vector. `min` and `max` are
+/// optional and become the range of every component, used to clamp loaded values.
+#[macro_export]
+macro_rules! controls {
+    (@control $group:ident, $name:literal, {default: $default:expr $(, min: $min:expr, max: $max:expr)? $(,)?}) => {
+      {
+        let value = $crate::controls::ControlValue::from($default);
+        $(
+          $group.ranges.insert($name, vec![$min as f64..=$max as f64; value.components().len()]);
+        )?
+        $group.commit($name, value);
+      }
+    };
+    (@group $group_name:literal, {$($name:literal: $properties:tt),* $(,)?}) => {
+      {
+        let mut group = $crate::controls::ControlGroup::new($group_name);
+        $(
+          $crate::controls!(@control group, $name, $properties);
+        )*
+        group
+      }
+    };
+    () => { $crate::controls::Controls::new() };
+    ($($group_name:literal: $group:tt),* $(,)?) => {
+      {
+        let mut controls = $crate::controls::Controls::new();
+        $(
+          controls.insert_group($crate::controls!(@group $group_name, $group));
+        )*
+        controls
+      }
+    };
+}
+
+#[derive(Debug)]
+pub struct Controls {
+  pub(crate) values: BTreeMap<&'static str, ControlGroup>,
+  speed_modifier: (Modifiers, f64),
+  pub selection: Selection,
+  pub dependencies: Dependencies,
+  pub inspector_sort: InspectorSort,
+  pub presets: BTreeMap<String, Preset>,
+  // the preset last saved or loaded, which tab badges count modified controls against
+  baseline: Option<String>,
+  pub active_tab: Option<&'static str>,
+  pub show_advanced: bool,
+  /// Sizes shared by every slider of the panel.
+  pub slider_style: SliderStyle,
+  solo: Option<&'static str>,
+  pinned: Vec<ControlPath>,
+  pins_restored: bool,
+  tabs: bool,
+  pub(crate) version: u32,
+  pub(crate) migrations: BTreeMap<u32, Migration>,
+  debounced: Vec<Debounced>,
+  recording: bool,
+  log: Vec<ChangeRecord>,
+  log_capacity: usize,
+  // frame time as of the last `tick`, what recorded changes are stamped with
+  now: f64,
+  replay: Option<Replay>,
+  uploaders: BTreeMap<&'static str, Uploader>,
+  push_constants: Vec<&'static str>,
+}
+
+/// One control that differs between two `Controls`. `old` is `None` for added controls and
+/// `new` is `None` for removed ones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlDiff {
+  pub group: &'static str,
+  pub name: &'static str,
+  pub old: Option<ControlValue>,
+  pub new: Option<ControlValue>,
+}
+
+/// A loaded value that was outside its control's range and got clamped into it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clamped {
+  pub group: &'static str,
+  pub name: &'static str,
+  pub loaded: ControlValue,
+  pub clamped: ControlValue,
+}
+
+const BIN_MAGIC: &[u8; 4] = b"RUIB";
+const BIN_VERSION: u32 = 1;
+
+/// One committed change, see `Controls::record`. `time` is egui's frame time in seconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeRecord {
+  pub group: &'static str,
+  pub name: &'static str,
+  pub value: ControlValue,
+  pub time: f64,
+}
+
+#[derive(Debug)]
+struct Replay {
+  records: Vec<ChangeRecord>,
+  speed: f32,
+  // frame time the replay started at, set by the first `tick`
+  started_at: Option<f64>,
+  next: usize,
+}
+
+// what a transaction restores of a group
+struct GroupSnapshot {
+  values: BTreeMap<&'static str, ControlValue>,
+  order: Vec<&'static str>,
+  packed: Vec<u8>,
+  needs_update: bool,
+  upload_dirty: bool,
+  size: usize,
+  defines_changed: bool,
+  uploaded: BTreeMap<&'static str, Vec<f64>>,
+}
+
+/// A snapshot of every control's value.
+pub type Preset = BTreeMap<ControlPath, ControlValue>;
+
+impl Controls {
+  pub fn new() -> Controls {
+    Controls {
+      values: BTreeMap::new(),
+      speed_modifier: (Modifiers::ALT, 10.0),
+      selection: Selection::default(),
+      dependencies: Dependencies::default(),
+      inspector_sort: InspectorSort::default(),
+      presets: BTreeMap::new(),
+      baseline: None,
+      active_tab: None,
+      show_advanced: false,
+      slider_style: SliderStyle::default(),
+      solo: None,
+      pinned: Vec::new(),
+      pins_restored: false,
+      tabs: false,
+      version: 1,
+      migrations: BTreeMap::new(),
+      debounced: Vec::new(),
+      recording: false,
+      log: Vec::new(),
+      log_capacity: 10_000,
+      now: 0.0,
+      replay: None,
+      uploaders: BTreeMap::new(),
+      push_constants: Vec::new(),
+    }
+  }
+
+  /// Makes `target` a read-only control computed by `f` from `inputs`, recomputed whenever
+  /// any input changes. Definitions that would form a cycle are rejected.
+  ///
+  /// ```ignore
+  /// controls.define(("view", "inv_resolution"), &[("view", "resolution")], |v| match v[0] {
+  ///   ControlValue::Vec2([x, y], ..) => ControlValue::from([1.0 / x, 1.0 / y]),
+  ///   _ => unreachable!(),
+  /// })?;
+  /// ```
+  pub fn define(&mut self, target: ControlPath, inputs: &[ControlPath], f: impl Fn(&[ControlValue]) -> ControlValue + 'static) -> Result<&mut Self, ControlError> {
+    if !self.dependencies.define(target, inputs, Box::new(f)) {
+      return Err(ControlError::DependencyCycle(format!("{}.{}", target.0, target.1)));
+    }
+    self.recompute();
+    Ok(self)
+  }
+
+  /// Recomputes derived controls whose inputs changed. Called after every `group`.
+  pub fn recompute(&mut self) {
+    self.dependencies.propagate(&mut self.values);
+    self.drain_journals();
+  }
+
+  /// Scales the drag speed of every slider by `multiplier` while `modifier` is held.
+  /// This multiplies each slider's own `drag_speed` rather than replacing it, and
+  /// drops back to 1x as soon as the modifier is released. Defaults to 10x on Alt.
+  pub fn speed_modifier(&mut self, modifier: Modifiers, multiplier: f64) -> &mut Self {
+    self.speed_modifier = (modifier, multiplier);
+    self
+  }
+
+  /// Writes `to_json` to `path`.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn save_json(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+    std::fs::write(path, self.to_json())
+  }
+
+  /// Adds a group built outside the panel, e.g. by `controls!`, replacing any group of
+  /// the same name.
+  pub fn insert_group(&mut self, group: ControlGroup) -> &mut Self {
+    self.values.insert(group.name, group);
+    self
+  }
+
+  pub fn group(&mut self, name: &'static str, build: impl FnOnce(ControlGroupBuilder) -> ControlGroupBuilder) -> &mut Self {
+    self.try_group(name, |builder| Ok(build(builder))).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  /// Like `group`, but lets the builder use the `try_` methods and propagate their errors.
+  pub fn try_group(&mut self, name: &'static str, build: impl FnOnce(ControlGroupBuilder) -> Result<ControlGroupBuilder, ControlError>) -> Result<&mut Self, ControlError> {
+    if !self.values.contains_key(name) {
+      self.values.insert(name, ControlGroup::new(name));
+      self.apply_solo();
+    }
+
+    // taken out of the map while building, so the builder can read the other groups
+    let (modifier, multiplier) = self.speed_modifier;
+    let mut group = self.values.remove(name).unwrap();
+    let builder = ControlGroupBuilder::new(&mut group)
+      .speed_modifier(modifier, multiplier)
+      .selection(&mut self.selection)
+      .readonly(self.dependencies.derived_in(name))
+      .visible(!self.tabs || self.active_tab == Some(name))
+      .show_advanced(self.show_advanced)
+      .others(&self.values)
+      .slider_style(self.slider_style);
+
+    let built = build(builder).map(|_| ());
+    self.values.insert(name, group);
+    built?;
+    self.recompute();
+
+    Ok(self)
+  }
+
   pub fn action_group(&mut self, ui: &mut eframe::egui::Ui, name: &'static str, build: impl FnOnce(ActionGroupBuilder) -> ActionGroupBuilder) -> &mut Self {
     let mut action_group = ActionGroup::new(name);
     build(ActionGroupBuilder::new(&mut action_group));
     ui.horizontal(|ui| {
-      for (title, action) in &mut action_group.actions {
-        if ui.button(*title).clicked() {
+      for item in &mut action_group.actions {
+        let (title, action) = match item {
+          ActionItem::Button(title, action) => (*title, action),
+          ActionItem::Separator => { ui.separator(); continue; },
+          ActionItem::Label(text) => { ui.label(*text); continue; },
+        };
+        let shortcut = action_group.shortcuts.get(title);
+        let label = match shortcut {
+          Some(shortcut) => format!("{} ({})", title, ui.ctx().format_shortcut(shortcut)),
+          None => title.to_string(),
+        };
+        let pressed = shortcut.map_or(false, |shortcut| ui.input_mut(|i| i.consume_shortcut(shortcut)));
+        if ui.button(label).clicked() || pressed {
           action(self);
         }
       }
     });
 
-    ui.add_space(20.0);
+    ui.add_space(20.0);
+    ui.separator();
+    self
+  }
+
+  /// Calls `callback` once the control at `path` has stopped changing for `delay_ms`, rather
+  /// than on every intermediate value of a drag. Needs `tick` to be called every frame.
+  pub fn on_change_debounced(&mut self, path: ControlPath, delay_ms: u64, callback: impl FnMut(&ControlValue) + 'static) -> &mut Self {
+    let last_seen = self.values.get(path.0).and_then(|g| g.values.get(path.1)).cloned();
+    self.debounced.push(Debounced { path, delay: delay_ms as f64 / 1000.0, callback: Box::new(callback), last_seen, changed_at: None });
+    self
+  }
+
+  /// Fires debounced callbacks whose control has settled, and keeps frames coming until
+  /// the pending ones have fired.
+  pub fn tick(&mut self, ctx: &eframe::egui::Context) {
+    let now = ctx.input(|i| i.time);
+    self.now = now;
+    self.advance_replay(ctx);
+    self.drain_journals();
+    for debounced in &mut self.debounced {
+      let (group, name) = debounced.path;
+      let Some(current) = self.values.get(group).and_then(|g| g.values.get(name)) else { continue };
+      if debounced.last_seen.as_ref() != Some(current) {
+        debounced.last_seen = Some(current.clone());
+        debounced.changed_at = Some(now);
+      }
+
+      let Some(changed_at) = debounced.changed_at else { continue };
+      let remaining = changed_at + debounced.delay - now;
+      if remaining <= 0.0 {
+        debounced.changed_at = None;
+        (debounced.callback)(current);
+      } else {
+        ctx.request_repaint_after(std::time::Duration::from_secs_f64(remaining));
+      }
+    }
+  }
+
+  /// Runs `f`, and if it fails, undoes every change it made to control values, dirty flags
+  /// and packed bytes, and drops the groups it created, before returning its error. Other
+  /// settings (ranges, tints, presets, ...) are kept either way.
+  pub fn transaction<E>(&mut self, f: impl FnOnce(&mut Controls) -> Result<(), E>) -> Result<(), E> {
+    let snapshot: BTreeMap<&'static str, GroupSnapshot> = self.values.iter().map(|(name, group)| (*name, GroupSnapshot {
+      values: group.values.inner.clone(),
+      order: group.values.order.clone(),
+      packed: group.packed.clone(),
+      needs_update: group.needs_update,
+      upload_dirty: group.upload_dirty,
+      size: group.size,
+      defines_changed: group.defines_changed,
+      uploaded: group.uploaded.clone(),
+    })).collect();
+    let dependencies = self.dependencies.snapshot();
+
+    let result = f(self);
+    if result.is_err() {
+      self.values.retain(|name, _| snapshot.contains_key(name));
+      for (name, saved) in snapshot {
+        let Some(group) = self.values.get_mut(name) else { continue };
+        group.values.inner = saved.values;
+        group.values.order = saved.order;
+        group.packed = saved.packed;
+        group.needs_update = saved.needs_update;
+        group.upload_dirty = saved.upload_dirty;
+        group.size = saved.size;
+        group.defines_changed = saved.defines_changed;
+        group.uploaded = saved.uploaded;
+      }
+      self.dependencies.restore(dependencies);
+    }
+    result
+  }
+
+  /// Registers what `flush` does with the packed bytes of `group`, e.g. a
+  /// `queue.write_buffer` into its uniform buffer. Replaces any previous uploader.
+  pub fn set_uploader(&mut self, group: &'static str, uploader: impl FnMut(&[u8]) + 'static) -> &mut Self {
+    self.uploaders.insert(group, Uploader(Box::new(uploader)));
+    self
+  }
+
+  /// Repacks every group that changed since its last upload and has an uploader, and hands
+  /// it the new bytes. Call once per frame before rendering.
+  pub fn flush(&mut self) {
+    for (name, uploader) in &mut self.uploaders {
+      let Some(group) = self.values.get_mut(name) else { continue };
+      if !group.upload_dirty { continue; }
+      group.upload_dirty = false;
+      (uploader.0)(group.get_bytes());
+    }
+  }
+
+  /// Names of the groups changed since they were last uploaded through `flush` or
+  /// `take_dirty`, i.e. whose buffers need uploading again. Packing them for any other
+  /// reason doesn't clear this.
+  pub fn dirty_groups(&self) -> Vec<&'static str> {
+    self.values.iter().filter(|(_, group)| group.upload_dirty).map(|(name, _)| *name).collect()
+  }
+
+  /// Repacks only the changed groups and returns their new bytes, clearing their upload
+  /// flags, for hosts that upload buffers themselves rather than through `set_uploader`.
+  pub fn take_dirty(&mut self) -> Vec<(&'static str, &[u8])> {
+    self.values.iter_mut().filter(|(_, group)| group.upload_dirty).map(|(name, group)| {
+      group.upload_dirty = false;
+      (*name, group.get_bytes())
+    }).collect()
+  }
+
+  /// While on, every committed change is appended to `change_log` with the time of the
+  /// last `tick`. The log keeps the most recent `set_log_capacity` changes.
+  pub fn record(&mut self, on: bool) -> &mut Self {
+    self.recording = on;
+    for group in self.values.values_mut() {
+      group.journal = on.then(Vec::new);
+    }
+    self
+  }
+
+  pub fn set_log_capacity(&mut self, capacity: usize) -> &mut Self {
+    self.log_capacity = capacity;
+    self.trim_log();
+    self
+  }
+
+  pub fn change_log(&self) -> &[ChangeRecord] {
+    &self.log
+  }
+
+  pub fn clear_change_log(&mut self) {
+    self.log.clear();
+  }
+
+  /// The change log as CSV with a `time,group,name,type,x,y,z,w` header, leaving unused
+  /// components empty.
+  pub fn change_log_csv(&self) -> String {
+    let mut csv = String::from("time,group,name,type,x,y,z,w\n");
+    for record in &self.log {
+      let mut components: Vec<String> = record.value.components().iter().map(|c| c.to_string()).collect();
+      components.resize(4, String::new());
+      csv.push_str(&format!("{},{},{},{},{}\n", record.time, record.group, record.name, record.value.type_name(), components.join(",")));
+    }
+    csv
+  }
+
+  /// Re-applies `log` over time from the next `tick`, at `speed` times the recorded pace,
+  /// e.g. to reproduce a session from a bug report. Changes to controls that don't exist
+  /// or changed type are skipped. Replaces any replay already running.
+  pub fn replay(&mut self, log: &[ChangeRecord], speed: f32) -> &mut Self {
+    let mut records = log.to_vec();
+    records.sort_by(|a, b| a.time.total_cmp(&b.time));
+    self.replay = Some(Replay { records, speed, started_at: None, next: 0 });
+    self
+  }
+
+  pub fn is_replaying(&self) -> bool {
+    self.replay.is_some()
+  }
+
+  pub fn stop_replay(&mut self) {
+    self.replay = None;
+  }
+
+  fn advance_replay(&mut self, ctx: &eframe::egui::Context) {
+    let Some(replay) = &mut self.replay else { return };
+    let Some(first) = replay.records.first().map(|r| r.time) else {
+      self.replay = None;
+      return;
+    };
+    let started_at = *replay.started_at.get_or_insert(self.now);
+    let elapsed = (self.now - started_at) * replay.speed as f64;
+
+    while let Some(record) = replay.records.get(replay.next) {
+      if record.time - first > elapsed { break; }
+      replay.next += 1;
+      let Some(group) = self.values.get_mut(record.group) else { continue };
+      let Some((&name, current)) = group.values.inner.get_key_value(record.name) else { continue };
+      if std::mem::discriminant(current) == std::mem::discriminant(&record.value) {
+        group.commit(name, record.value.clone());
+      }
+    }
+
+    if replay.next >= replay.records.len() {
+      self.replay = None;
+    } else {
+      ctx.request_repaint();
+    }
+    self.recompute();
+  }
+
+  fn drain_journals(&mut self) {
+    if !self.recording { return; }
+    for (group_name, group) in &mut self.values {
+      let journal = group.journal.get_or_insert_with(Vec::new);
+      for (name, value) in journal.drain(..) {
+        self.log.push(ChangeRecord { group: *group_name, name, value, time: self.now });
+      }
+    }
+    self.trim_log();
+  }
+
+  fn trim_log(&mut self) {
+    if self.log.len() > self.log_capacity {
+      self.log.drain(..self.log.len() - self.log_capacity);
+    }
+  }
+
+  /// Sets a control from code, clamped into the range its builder last declared, and
+  /// recomputes derived controls. Returns the value the control actually holds, which is
+  /// also what change callbacks see. Fails if the control doesn't exist or holds a
+  /// different type.
+  pub fn set(&mut self, path: ControlPath, value: ControlValue) -> Result<ControlValue, ControlError> {
+    let (group, name) = path;
+    let target = self.try_get_mut(group)?;
+    let current = target.try_get(name)?;
+    if std::mem::discriminant(current) != std::mem::discriminant(&value) {
+      return Err(ControlError::TypeMismatch { name: name.to_string(), expected: current.type_name() });
+    }
+    let value = match target.ranges.get(name) {
+      Some(ranges) => value.clamp_components(ranges).unwrap_or(value),
+      None => value,
+    };
+    target.commit(name, value);
+    // what the control holds after the commit, e.g. snapped to a power of two
+    let value = target.values.get(name).unwrap().clone();
+    self.recompute();
+    Ok(value)
+  }
+
+  /// Sets a control the way a user edit would, without egui: the value is clamped like
+  /// `set`, committed and marked dirty, derived controls recompute, and change callbacks
+  /// for the control fire. With no frame clock to wait on, debounced callbacks fire
+  /// straight away.
+  #[cfg(feature = "testing")]
+  pub fn simulate_set(&mut self, group: &'static str, name: &'static str, value: ControlValue) -> Result<(), ControlError> {
+    self.set((group, name), value)?;
+
+    for debounced in &mut self.debounced {
+      let (group, name) = debounced.path;
+      let Some(current) = self.values.get(group).and_then(|g| g.values.get(name)) else { continue };
+      if debounced.last_seen.as_ref() != Some(current) {
+        debounced.last_seen = Some(current.clone());
+        debounced.changed_at = None;
+        (debounced.callback)(current);
+      }
+    }
+    Ok(())
+  }
+
+  /// What changed going from `self` to `other`, ordered by group name, then by control
+  /// order with controls only in `other` last.
+  pub fn diff(&self, other: &Controls) -> Vec<ControlDiff> {
+    let names: BTreeSet<&'static str> = self.values.keys().chain(other.values.keys()).copied().collect();
+    let mut diffs = Vec::new();
+    for group in names {
+      let (old, new) = (self.values.get(group), other.values.get(group));
+      let old_values = old.into_iter().flat_map(|g| g.values.iter());
+      for (name, value) in old_values {
+        let new_value = new.and_then(|g| g.values.get(*name));
+        if new_value != Some(value) {
+          diffs.push(ControlDiff { group, name: *name, old: Some(value.clone()), new: new_value.cloned() });
+        }
+      }
+
+      let new_values = new.into_iter().flat_map(|g| g.values.iter());
+      for (name, value) in new_values {
+        if old.and_then(|g| g.values.get(*name)).is_none() {
+          diffs.push(ControlDiff { group, name: *name, old: None, new: Some(value.clone()) });
+        }
+      }
+    }
+    diffs
+  }
+
+  pub fn save_preset(&mut self, name: &str) {
+    let mut preset = Preset::new();
+    for (g, group) in &self.values {
+      for (n, value) in &group.values {
+        preset.insert((*g, *n), value.clone());
+      }
+    }
+    self.presets.insert(name.to_string(), preset);
+    self.baseline = Some(name.to_string());
+  }
+
+  pub fn load_preset(&mut self, name: &str) -> Result<(), ControlError> {
+    self.lerp_presets(name, name, 0.0)?;
+    self.baseline = Some(name.to_string());
+    Ok(())
+  }
+
+  /// Number of controls in `group` that differ from the preset last saved or loaded, or
+  /// that it doesn't contain. Zero until a preset has been saved or loaded.
+  pub fn modified_count(&self, group: &'static str) -> usize {
+    let Some(preset) = self.baseline.as_ref().and_then(|name| self.presets.get(name)) else { return 0 };
+    let Some(values) = self.values.get(group) else { return 0 };
+    values.values.iter().filter(|(name, value)| preset.get(&(group, **name)) != Some(*value)).count()
+  }
+
+  /// Applies the interpolation between presets `a` and `b` at `t`. Controls missing from
+  /// either preset, whose type changed or whose shapes differ between the presets keep
+  /// their current value.
+  pub fn lerp_presets(&mut self, a: &str, b: &str, t: f32) -> Result<(), ControlError> {
+    let preset_a = self.presets.get(a).ok_or_else(|| ControlError::UnknownPreset(a.to_string()))?;
+    let preset_b = self.presets.get(b).ok_or_else(|| ControlError::UnknownPreset(b.to_string()))?;
+
+    for ((g, n), value_a) in preset_a {
+      let Some(value) = preset_b.get(&(*g, *n)).and_then(|value_b| value_a.lerp(value_b, t)) else { continue };
+      let Some(group) = self.values.get_mut(g) else { continue };
+      if group.values.get(*n).map_or(false, |current| std::mem::discriminant(current) == std::mem::discriminant(&value)) {
+        group.commit(*n, value);
+      }
+    }
+
+    self.recompute();
+    Ok(())
+  }
+
+  /// Draws a tab per group. From then on only the active tab's group is drawn by `group`,
+  /// the others keep their values without taking up space. Call before the groups.
+  /// Each tab has a badge counting the controls modified since the last preset and
+  /// flagging groups that still have to be repacked.
+  pub fn show_tabs(&mut self, ui: &mut eframe::egui::Ui) -> &mut Self {
+    self.tabs = true;
+    if self.active_tab.map_or(true, |tab| !self.values.contains_key(tab)) {
+      self.active_tab = self.values.iter().find(|(_, g)| g.values.iter().next().is_some()).map(|(name, _)| *name);
+    }
+
+    let modified: BTreeMap<&'static str, usize> = self.values.keys().map(|name| (*name, self.modified_count(name))).collect();
+    ui.horizontal_wrapped(|ui| {
+      for (name, group) in &self.values {
+        if group.values.iter().next().is_none() { continue; }
+        if ui.selectable_label(self.active_tab == Some(*name), *name).clicked() {
+          self.active_tab = Some(*name);
+        }
+        tab_badge(ui, modified[name], group.needs_update);
+      }
+    });
+    ui.separator();
+    self
+  }
+
+  /// Also draws the control at `(group, name)` in the section shown by `show_pinned`.
+  pub fn pin(&mut self, group: &'static str, name: &'static str) -> &mut Self {
+    if !self.pinned.contains(&(group, name)) {
+      self.pinned.push((group, name));
+    }
+    self
+  }
+
+  pub fn unpin(&mut self, group: &'static str, name: &'static str) -> &mut Self {
+    self.pinned.retain(|path| *path != (group, name));
+    self
+  }
+
+  pub fn is_pinned(&self, group: &'static str, name: &'static str) -> bool {
+    self.pinned.contains(&(group, name))
+  }
+
+  /// Draws the pinned controls, in the order they were pinned, editing the same values as
+  /// their groups. The pins are kept in egui's persisted memory. Call before the groups.
+  pub fn show_pinned(&mut self, ui: &mut eframe::egui::Ui) -> &mut Self {
+    let id = Id::new("rustui_pinned");
+    if !std::mem::replace(&mut self.pins_restored, true) {
+      let saved = ui.data_mut(|d| d.get_persisted::<Vec<(String, String)>>(id)).unwrap_or_default();
+      for (group, name) in saved {
+        let path = (leak(&group), leak(&name));
+        if !self.pinned.contains(&path) { self.pinned.push(path); }
+      }
+    }
+    let saved: Vec<(String, String)> = self.pinned.iter().map(|(g, n)| (g.to_string(), n.to_string())).collect();
+    ui.data_mut(|d| d.insert_persisted(id, saved));
+    if self.pinned.is_empty() { return self; }
+
+    ui.label("Pinned");
+    let mut edited = false;
+    for (g, n) in self.pinned.clone() {
+      let Some(group) = self.values.get_mut(g) else { continue };
+      let Some(value) = group.values.get(n).cloned() else { continue };
+      let enabled = !self.dependencies.is_derived((g, n)) && !group.is_overridden(n);
+      let title = format!("{}.{}", g, n);
+
+      if let ControlValue::Bool(v, _, _) = value {
+        let mut checked = v != 0;
+        if ui.add_enabled(enabled, eframe::egui::Checkbox::new(&mut checked, title)).changed() {
+          group.commit(n, ControlValue::from(checked));
+          edited = true;
+        }
+        continue;
+      }
+
+      let components = value.components();
+      let count = components.len();
+      let ranges = group.ranges.get(n).cloned().unwrap_or_default();
+      let tint = group.tint_of(n);
+      for (i, c) in components.into_iter().enumerate() {
+        let range = ranges.get(i).cloned().unwrap_or(0.0..=1.0);
+        let title = if count > 1 { format!("{}.{}", title, component_name(i, count)) } else { title.clone() };
+        let mut current = c;
+        ui.add_enabled(enabled, Slider::from_get_set(range, |v| {
+          if let Some(v) = v { current = v; }
+          current
+        }).with_title(title.as_str()).speed_modifier(self.speed_modifier.0, self.speed_modifier.1).tint(tint).style(self.slider_style));
+        if current != c {
+          let current = if value.is_integer() { current.round() } else { current };
+          let latest = group.values.get(n).unwrap();
+          if let Some(updated) = latest.with_component(i, current) {
+            group.commit(n, updated);
+            edited = true;
+          }
+        }
+      }
+    }
     ui.separator();
+    if edited { self.recompute(); }
+    self
+  }
+
+  /// Packs every group other than `name` as zeros, to see one group's contribution alone.
+  /// Soloing another group moves the solo there.
+  pub fn solo(&mut self, name: &'static str) -> Result<&mut Self, ControlError> {
+    if !self.values.contains_key(name) {
+      return Err(ControlError::UnknownGroup(name.to_string()));
+    }
+    self.solo = Some(name);
+    self.apply_solo();
+    Ok(self)
+  }
+
+  /// Packs every group normally again.
+  pub fn clear_solo(&mut self) -> &mut Self {
+    self.solo = None;
+    self.apply_solo();
+    self
+  }
+
+  pub fn soloed(&self) -> Option<&'static str> {
+    self.solo
+  }
+
+  fn apply_solo(&mut self) {
+    for (name, group) in &mut self.values {
+      let zeroed = self.solo.map_or(false, |solo| solo != *name);
+      if group.zeroed != zeroed {
+        group.zeroed = zeroed;
+        group.mark_dirty();
+      }
+    }
+  }
+
+  /// Packs every non-empty group into one buffer, in group name order. Returns the buffer
+  /// and each group's `(name, offset, size)` in it; offsets are multiples of 16.
+  pub fn pack_all(&mut self) -> (Vec<u8>, Vec<(&'static str, usize, usize)>) {
+    let mut buffer = Vec::new();
+    let mut table = Vec::new();
+    for (name, group) in &mut self.values {
+      if group.is_empty() { continue; }
+      let bytes = group.get_bytes();
+      table.push((*name, buffer.len(), bytes.len()));
+      buffer.extend_from_slice(bytes);
+    }
+    (buffer, table)
+  }
+
+  /// Picks the groups `pack_push_constants` packs, in that order.
+  pub fn set_push_constant_groups(&mut self, groups: &[&'static str]) -> &mut Self {
+    self.push_constants = groups.to_vec();
+    self
+  }
+
+  /// Packs the push constant groups back to back into one block, each starting on a
+  /// 16-byte boundary. Fails if a group doesn't exist or the block exceeds `max_size`,
+  /// typically 128 or 256 bytes depending on the device.
+  pub fn pack_push_constants(&mut self, max_size: usize) -> Result<Vec<u8>, ControlError> {
+    let mut block = Vec::new();
+    for name in self.push_constants.clone() {
+      block.extend_from_slice(self.try_get_mut(name)?.get_bytes());
+    }
+    if block.len() > max_size {
+      return Err(ControlError::PushConstantsTooLarge { size: block.len(), max_size });
+    }
+    Ok(block)
+  }
+
+  /// `pack_all` preceded by a header describing it, for offline renderers and non-Rust
+  /// tools. All integers are little-endian u32:
+  ///
+  /// ```text
+  /// "RUIB"  version (1)  group count
+  /// per group: name length, name (UTF-8), offset, size
+  /// packed bytes, with offsets relative to their start
+  /// ```
+  pub fn export_bin_bytes(&mut self) -> Vec<u8> {
+    let (buffer, table) = self.pack_all();
+    let mut bytes = Vec::with_capacity(buffer.len() + 12 + table.len() * 32);
+    bytes.extend_from_slice(BIN_MAGIC);
+    bytes.extend_from_slice(&BIN_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(table.len() as u32).to_le_bytes());
+    for (name, offset, size) in table {
+      bytes.extend_from_slice(&(name.len() as u32).to_le_bytes());
+      bytes.extend_from_slice(name.as_bytes());
+      bytes.extend_from_slice(&(offset as u32).to_le_bytes());
+      bytes.extend_from_slice(&(size as u32).to_le_bytes());
+    }
+    bytes.extend_from_slice(&buffer);
+    bytes
+  }
+
+  /// Writes `export_bin_bytes` to `path`.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn export_bin(&mut self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+    std::fs::write(path, self.export_bin_bytes())
+  }
+
+  /// Gives every label of the panel a `width` wide column left of its control, so tracks
+  /// line up across rows like a property grid. Labels that don't fit are cut short.
+  pub fn label_column(&mut self, width: f32) -> &mut Self {
+    self.slider_style.title_width = width;
+    self.slider_style.label_column = true;
     self
   }
 
-  pub fn get(&self, name: &'static str) -> &ControlGroup {
-    self.values.get(name).unwrap()
+  /// Draws the "Show advanced" checkbox. The setting is kept in egui's persisted memory so
+  /// it survives restarts when persistence is enabled. Call before the groups.
+  pub fn show_advanced_toggle(&mut self, ui: &mut eframe::egui::Ui) -> &mut Self {
+    let id = Id::new("rustui_show_advanced");
+    let mut on = ui.data_mut(|d| *d.get_persisted_mut_or_default::<bool>(id));
+    if ui.checkbox(&mut on, "Show advanced").changed() {
+      ui.data_mut(|d| d.insert_persisted(id, on));
+    }
+    self.show_advanced = on;
+    self
+  }
+
+  /// Values of the bool controls marked with `shader_define`, for compiling into the shader
+  /// as `#define`s or specialization constants rather than uploading as uniforms.
+  pub fn specialization_constants(&self) -> BTreeMap<&'static str, bool> {
+    let mut constants = BTreeMap::new();
+    for group in self.values.values() {
+      for (name, value) in &group.values {
+        if let (true, ControlValue::Bool(v, _, _)) = (group.defines.contains(name), value) {
+          constants.insert(*name, *v != 0);
+        }
+      }
+    }
+    constants
+  }
+
+  /// Returns true, once, after any shader define changed and the shaders need recompiling.
+  pub fn take_recompile(&mut self) -> bool {
+    let mut changed = false;
+    for group in self.values.values_mut() {
+      changed |= std::mem::take(&mut group.defines_changed);
+    }
+    changed
+  }
+
+  /// Shows every control of every group in a single sortable, editable table window.
+  pub fn show_inspector(&mut self, ctx: &eframe::egui::Context) {
+    let mut rows: Vec<(&'static str, &'static str)> = self.values.iter()
+      .flat_map(|(g, group)| group.values.iter().map(move |(n, _)| (*g, *n)))
+      .collect();
+    match self.inspector_sort {
+      InspectorSort::Group => rows.sort_by_key(|&(g, _)| g),
+      InspectorSort::Name => rows.sort_by_key(|&(g, n)| (n, g)),
+      InspectorSort::Type => rows.sort_by_key(|&(g, n)| (self.values[&g].values.get(n).unwrap().type_name(), g, n)),
+    }
+
+    let mut sort = self.inspector_sort;
+    Window::new("Inspector").show(ctx, |ui| {
+      ScrollArea::vertical().show(ui, |ui| {
+        Grid::new("inspector").striped(true).num_columns(4).show(ui, |ui| {
+          for (title, key) in [("group", InspectorSort::Group), ("name", InspectorSort::Name), ("type", InspectorSort::Type)] {
+            if ui.selectable_label(sort == key, title).clicked() {
+              sort = key;
+            }
+          }
+          ui.label("value");
+          ui.end_row();
+
+          for (g, n) in &rows {
+            let group = self.values.get_mut(g).unwrap();
+            let value = group.values.get(*n).unwrap();
+            ui.label(*g);
+            ui.label(*n);
+            ui.label(value.type_name());
+            if let Some(updated) = edit_value(ui, value) {
+              group.commit(*n, updated);
+            }
+            ui.end_row();
+          }
+        });
+      });
+    });
+    self.inspector_sort = sort;
+  }
+
+  pub fn get(&self, key: impl ControlKey) -> &ControlGroup {
+    self.try_get(key).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn get_mut(&mut self, key: impl ControlKey) -> &mut ControlGroup {
+    self.try_get_mut(key).unwrap_or_else(|e| panic!("{}", e))
+  }
+
+  pub fn try_get(&self, key: impl ControlKey) -> Result<&ControlGroup, ControlError> {
+    let name = key.key();
+    self.values.get(name).ok_or_else(|| ControlError::UnknownGroup(name.to_string()))
   }
 
-  pub fn get_mut(&mut self, name: &'static str) -> &mut ControlGroup {
-    self.values.get_mut(name).unwrap()
+  pub fn try_get_mut(&mut self, key: impl ControlKey) -> Result<&mut ControlGroup, ControlError> {
+    let name = key.key();
+    self.values.get_mut(name).ok_or_else(|| ControlError::UnknownGroup(name.to_string()))
   }
 }
 
-impl Index<&'static str> for Controls {
+impl<K: ControlKey> Index<K> for Controls {
   type Output = ControlGroup;
-  fn index(&self, name: &'static str) -> &Self::Output { self.get(name) }
+  fn index(&self, key: K) -> &Self::Output { self.get(key) }
+}
+
+impl<K: ControlKey> IndexMut<K> for Controls {
+  fn index_mut(&mut self, key: K) -> &mut Self::Output { self.get_mut(key) }
 }
 
-impl IndexMut<&'static str> for Controls {
-  fn index_mut(&mut self, name: &'static str) -> &mut Self::Output { self.get_mut(name) }
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn group(name: &'static str, values: &[(&'static str, ControlValue)]) -> ControlGroup {
+    let mut group = ControlGroup::new(name);
+    for (name, value) in values {
+      group.commit(*name, value.clone());
+    }
+    group
+  }
+
+  // runs `f` once inside a headless frame, for builders that need a `Ui`
+  fn with_ui(f: impl FnOnce(&mut eframe::egui::Ui)) {
+    run_frame(&eframe::egui::Context::default(), Vec::new(), f);
+  }
+
+  // runs one frame of `ctx` receiving `events`, returning the area `f` drew into
+  fn run_frame(ctx: &eframe::egui::Context, events: Vec<eframe::egui::Event>, f: impl FnOnce(&mut eframe::egui::Ui)) -> eframe::egui::Rect {
+    let mut f = Some(f);
+    let mut drawn = eframe::egui::Rect::NOTHING;
+    let input = eframe::egui::RawInput { events, ..Default::default() };
+    let _ = ctx.run(input, |ctx| {
+      eframe::egui::CentralPanel::default().show(ctx, |ui| {
+        if let Some(f) = f.take() { f(ui); }
+        drawn = ui.min_rect();
+      });
+    });
+    drawn
+  }
+
+  #[test]
+  fn pow2_controls_snap_on_every_write() {
+    let mut size = ControlGroup::new("g");
+    size.pow2.insert("size");
+    size.ranges.insert("size", vec![1.0..=4096.0]);
+    size.commit("size", ControlValue::from(256));
+    let mut controls = Controls::new();
+    controls.insert_group(size);
+
+    assert_eq!(controls.set(("g", "size"), ControlValue::from(100)).unwrap(), ControlValue::from(128));
+    assert_eq!(controls["g"]["size"], ControlValue::from(128));
+    controls.set(("g", "size"), ControlValue::from(9000)).unwrap();
+    assert_eq!(controls["g"]["size"], ControlValue::from(4096));
+    controls.from_json(r#"{ "groups": { "g": { "size": { "type": "int", "value": 96 } } } }"#).unwrap();
+    assert_eq!(controls["g"]["size"], ControlValue::from(64));
+
+    controls.set(("g", "size"), ControlValue::from(16)).unwrap();
+    controls.save_preset("small");
+    controls.set(("g", "size"), ControlValue::from(1024)).unwrap();
+    controls.save_preset("large");
+    controls.lerp_presets("small", "large", 0.5).unwrap();
+    assert_eq!(controls["g"]["size"], ControlValue::from(512));
+  }
+
+  #[test]
+  fn optimized_layout_packs_smaller() {
+    let mut mixed = group("g", &[
+      ("a", ControlValue::from(1.0f32)),
+      ("p", ControlValue::from([1.0f32; 3])),
+      ("b", ControlValue::from(2.0f32)),
+      ("q", ControlValue::from([2.0f32; 3])),
+    ]);
+    assert_eq!(mixed.get_bytes().len(), 48);
+
+    let layout = mixed.optimize_layout().unwrap();
+    assert_eq!(layout, vec![("p", 0, 12), ("a", 12, 4), ("q", 16, 12), ("b", 28, 4)]);
+    assert_eq!(mixed.get_bytes().len(), 32);
+    assert_eq!(mixed.optimize_layout(), None);
+  }
+
+  #[test]
+  fn dirty_groups_survive_packing_until_taken() {
+    let mut controls = Controls::new();
+    controls.insert_group(group("a", &[("x", ControlValue::from(1.0f32))]));
+    controls.insert_group(group("b", &[("y", ControlValue::from(2))]));
+    assert_eq!(controls.dirty_groups(), vec!["a", "b"]);
+    assert_eq!(controls.take_dirty().len(), 2);
+    assert!(controls.dirty_groups().is_empty());
+
+    controls.set(("a", "x"), ControlValue::from(3.0f32)).unwrap();
+    // packing for anything else must not swallow the pending upload
+    controls.pack_all();
+    controls["a"].get_bytes();
+    assert_eq!(controls.dirty_groups(), vec!["a"]);
+
+    let taken: Vec<&str> = controls.take_dirty().into_iter().map(|(name, _)| name).collect();
+    assert_eq!(taken, vec!["a"]);
+    assert!(controls.dirty_groups().is_empty());
+  }
+
+  #[test]
+  fn flush_hands_out_bytes_packed_elsewhere() {
+    let uploads = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let mut controls = Controls::new();
+    controls.insert_group(group("a", &[("x", ControlValue::from(1.0f32))]));
+    let sink = uploads.clone();
+    controls.set_uploader("a", move |bytes| sink.borrow_mut().push(bytes.to_vec()));
+    controls.flush();
+
+    controls.set(("a", "x"), ControlValue::from(4.0f32)).unwrap();
+    controls["a"].iter_packed().count();
+    controls.flush();
+    controls.flush();
+    let uploads = uploads.borrow();
+    assert_eq!(uploads.len(), 2);
+    assert_eq!(&uploads[1][..4], &4.0f32.to_ne_bytes());
+  }
+
+  #[test]
+  fn lookups_report_what_went_wrong() {
+    let mut controls = Controls::new();
+    controls.insert_group(group("g", &[("x", ControlValue::from(1.0f32))]));
+
+    assert_eq!(controls.set(("h", "x"), ControlValue::from(1.0f32)), Err(ControlError::UnknownGroup("h".to_string())));
+    assert_eq!(controls.set(("g", "y"), ControlValue::from(1.0f32)), Err(ControlError::UnknownControl("y".to_string())));
+    assert_eq!(controls.set(("g", "x"), ControlValue::from(1)), Err(ControlError::TypeMismatch { name: "x".to_string(), expected: "float" }));
+    assert!(matches!(controls.from_json("{ not json"), Err(ControlError::ParseError(_))));
+    assert_eq!(controls["g"]["x"], ControlValue::from(1.0f32));
+  }
+
+  #[test]
+  fn vec3_members_align_to_16_bytes() {
+    let mut mixed = group("g", &[
+      ("a", ControlValue::from(1.0f32)),
+      ("v", ControlValue::from([2.0f32, 3.0, 4.0])),
+      ("b", ControlValue::from(5.0f32)),
+    ]);
+    assert_eq!(mixed.member_layout("a"), Some((0, 4)));
+    assert_eq!(mixed.member_layout("v"), Some((16, 12)));
+    assert_eq!(mixed.member_layout("b"), Some((28, 4)));
+
+    let bytes = mixed.get_bytes();
+    assert_eq!(bytes.len(), 32);
+    assert_eq!(&bytes[4..16], &[0; 12]);
+    assert_eq!(&bytes[16..20], &2.0f32.to_ne_bytes());
+    assert_eq!(&bytes[28..32], &5.0f32.to_ne_bytes());
+  }
+
+  #[cfg(feature = "f16")]
+  #[test]
+  fn half_vec2_packs_to_4_bytes() {
+    let mut small = ControlGroup::new("g");
+    small.half.insert("v");
+    small.commit("v", ControlValue::from([1.0f32, -2.0]));
+    assert_eq!(small.member_layout("v"), Some((0, 4)));
+    assert_eq!(small.unpadded_size(), 4);
+    let expected: Vec<u8> = [1.0f32, -2.0].iter().flat_map(|c| half::f16::from_f32(*c).to_ne_bytes()).collect();
+    assert_eq!(&small.get_bytes()[..4], &expected[..]);
+  }
+
+  #[test]
+  fn repacking_into_the_old_buffer_matches_a_fresh_pack() {
+    let before: [&[(&'static str, ControlValue)]; 3] = [
+      &[("x", ControlValue::from(1.0f32)), ("v", ControlValue::from([1.0f32; 3]))],
+      &[("i", ControlValue::from(3)), ("m", ControlValue::from([0.5f32; 4]))],
+      &[("on", ControlValue::from(true)), ("w", ControlValue::from([2.0f32; 2]))],
+    ];
+    let after: [&[(&'static str, ControlValue)]; 3] = [
+      &[("x", ControlValue::from(-4.0f32)), ("v", ControlValue::from([7.0f32, 8.0, 9.0]))],
+      &[("i", ControlValue::from(-1)), ("m", ControlValue::from([0.0f32, 1.0, 2.0, 3.0]))],
+      &[("on", ControlValue::from(false)), ("w", ControlValue::from([6.0f32, 5.0]))],
+    ];
+    for (before, after) in before.iter().zip(after) {
+      let mut reused = group("g", before);
+      reused.get_bytes();
+      for (name, value) in after {
+        reused.commit(*name, value.clone());
+      }
+      assert_eq!(reused.get_bytes(), group("g", after).get_bytes());
+    }
+  }
+
+  #[test]
+  fn empty_groups_pack_to_nothing() {
+    let mut empty = ControlGroup::new("g");
+    assert!(empty.is_empty());
+    assert_eq!(empty.unpadded_size(), 0);
+    assert_eq!(empty.aligned_size(), 0);
+    assert!(empty.get_bytes().is_empty());
+    assert_eq!(empty.iter_packed().count(), 0);
+  }
+
+  #[test]
+  fn failed_transactions_leave_controls_unchanged() {
+    let mut controls = Controls::new();
+    controls.insert_group(group("g", &[("x", ControlValue::from(1.0f32)), ("y", ControlValue::from(2))]));
+    let packed = controls["g"].get_bytes().to_vec();
+
+    let result: Result<(), ControlError> = controls.transaction(|c| {
+      c.set(("g", "x"), ControlValue::from(5.0f32))?;
+      c.insert_group(group("h", &[("z", ControlValue::from(3))]));
+      c.set(("g", "y"), ControlValue::from(true))?;
+      Ok(())
+    });
+    assert!(matches!(result, Err(ControlError::TypeMismatch { .. })));
+    assert_eq!(controls["g"]["x"], ControlValue::from(1.0f32));
+    assert_eq!(controls["g"]["y"], ControlValue::from(2));
+    assert!(controls.try_get("h").is_err());
+    assert_eq!(controls["g"].get_bytes(), &packed[..]);
+    assert!(controls.dirty_groups().contains(&"g"));
+
+    controls.transaction(|c| c.set(("g", "x"), ControlValue::from(5.0f32)).map(drop)).unwrap();
+    assert_eq!(controls["g"]["x"], ControlValue::from(5.0f32));
+  }
+
+  #[test]
+  fn controls_macro_declares_groups_in_order() {
+    let mut controls = crate::controls! {
+      "lighting": {
+        "intensity": { default: 0.5, min: 0.0, max: 1.0 },
+        "dir": { default: [0.0, 1.0, 0.0] },
+        "bounces": { default: 2 },
+      },
+      "fog": {
+        "density": { default: 0.1 },
+      },
+    };
+    assert_eq!(controls.values.keys().copied().collect::<Vec<_>>(), vec!["fog", "lighting"]);
+    assert_eq!(controls["lighting"].values.order, vec!["intensity", "dir", "bounces"]);
+    assert_eq!(controls["lighting"]["bounces"], ControlValue::from(2));
+    assert_eq!(controls["lighting"].ranges["intensity"], vec![0.0..=1.0]);
+    // float at 0, vec3 aligned to 16, the int in its last 4 bytes
+    assert_eq!(controls["lighting"].get_bytes().len(), 32);
+    assert_eq!(controls["fog"].get_bytes().len(), 16);
+  }
+
+  #[test]
+  fn action_buttons_keep_insertion_order() {
+    let mut actions = ActionGroup::new("file");
+    ActionGroupBuilder::new(&mut actions)
+      .button("save", |_| {})
+      .separator()
+      .button("load", |_| {})
+      .button("export", |_| {})
+      .button("save", |_| {});
+    let names: Vec<&str> = actions.actions.iter().map(|item| match item {
+      ActionItem::Button(name, _) => name,
+      ActionItem::Separator => "|",
+      ActionItem::Label(text) => text,
+    }).collect();
+    assert_eq!(names, vec!["save", "|", "load", "export"]);
+  }
+
+  #[test]
+  fn strict_builders_flag_redeclarations_with_another_type() {
+    let mut strict = ControlGroup::new("g");
+    let mut lenient = ControlGroup::new("g");
+    with_ui(|ui| {
+      let builder = ControlGroupBuilder::new(&mut strict).strict(true).try_float(ui, "bias", 1.0, 0.0..=1.0).unwrap();
+      let builder = builder.try_float(ui, "bias", 0.5, 0.0..=1.0).unwrap();
+      let conflict = builder.try_int(ui, "bias", 1, 0..=4).err();
+      assert_eq!(conflict, Some(ControlError::DuplicateControl("g.bias".to_string())));
+
+      let builder = ControlGroupBuilder::new(&mut lenient).try_float(ui, "bias", 1.0, 0.0..=1.0).unwrap();
+      let conflict = builder.try_int(ui, "bias", 1, 0..=4).err();
+      assert!(matches!(conflict, Some(ControlError::TypeMismatch { .. })));
+    });
+    assert_eq!(strict["bias"], ControlValue::from(1.0f32));
+  }
+
+  #[test]
+  fn inspectable_values_round_trip_through_f64_slices() {
+    let pairs = [
+      (ControlValue::from(1), ControlValue::from(-7)),
+      (ControlValue::from(false), ControlValue::from(true)),
+      (ControlValue::from(0.0f32), ControlValue::from(2.5f32)),
+      (ControlValue::from([0.0f32; 2]), ControlValue::from([1.0f32, -1.0])),
+      (ControlValue::from([0.0f32; 3]), ControlValue::from([1.0f32, 2.0, 3.0])),
+      (ControlValue::from([0.0f32; 4]), ControlValue::from([1.0f32, 2.0, 3.0, 4.0])),
+      (ControlValue::from(vec![(0.0f32, [0.0f32; 4]), (1.0, [1.0; 4])]), ControlValue::from(vec![(0.25f32, [1.0f32, 0.0, 0.0, 1.0]), (0.75, [0.0, 0.0, 1.0, 0.5])])),
+      (ControlValue::from([[0.0f32; 3]; 3]), ControlValue::from([[1.0f32, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]])),
+      (ControlValue::from([[0.0f32; 4]; 4]), ControlValue::from([[1.0f32, 0.0, 0.0, 0.0], [0.0, 2.0, 0.0, 0.0], [0.0, 0.0, 3.0, 0.0], [4.0, 5.0, 6.0, 1.0]])),
+      (ControlValue::from([0i32; 2]), ControlValue::from([3i32, -4])),
+      (ControlValue::from([0i32; 3]), ControlValue::from([3i32, -4, 5])),
+      (ControlValue::from([0i32; 4]), ControlValue::from([3i32, -4, 5, -6])),
+      (ControlValue::from((vec![[0.0f32, 0.0], [1.0, 1.0]], 8)), ControlValue::from((vec![[0.0f32, 1.0], [1.0, 0.25]], 8))),
+    ];
+    for (mut value, target) in pairs {
+      let components = target.value_as_f64_slice();
+      assert!(!value.set_from_f64_slice(&components[1..]), "{} took a short slice", Inspectable::type_name(&value));
+      assert!(value.set_from_f64_slice(&components));
+      assert_eq!(value, target);
+      assert_eq!(value.value_as_f64_slice(), components);
+    }
+  }
+
+  #[test]
+  fn flush_uploads_changed_groups_with_an_uploader() {
+    let uploads = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let mut controls = Controls::new();
+    controls.insert_group(group("a", &[("x", ControlValue::from(1.0f32))]));
+    controls.insert_group(group("b", &[("y", ControlValue::from(2))]));
+    controls.insert_group(group("c", &[("z", ControlValue::from(3))]));
+    for name in ["a", "b"] {
+      let sink = uploads.clone();
+      controls.set_uploader(name, move |bytes| sink.borrow_mut().push((name, bytes.to_vec())));
+    }
+
+    controls.flush();
+    let expected = vec![("a", controls["a"].get_bytes().to_vec()), ("b", controls["b"].get_bytes().to_vec())];
+    assert_eq!(*uploads.borrow(), expected);
+    assert_eq!(controls.dirty_groups(), vec!["c"]);
+
+    uploads.borrow_mut().clear();
+    controls.flush();
+    assert!(uploads.borrow().is_empty());
+    controls.set(("b", "y"), ControlValue::from(5)).unwrap();
+    controls.flush();
+    assert_eq!(*uploads.borrow(), vec![("b", controls["b"].get_bytes().to_vec())]);
+  }
+
+  #[test]
+  fn iter_packed_walks_the_packed_buffer() {
+    let mut mixed = group("g", &[
+      ("i", ControlValue::from(7)),
+      ("v", ControlValue::from([1.0f32, 2.0, 3.0])),
+      ("w", ControlValue::from([4.0f32, 5.0])),
+      ("m", ControlValue::from([[1.0f32; 3]; 3])),
+    ]);
+    let packed = mixed.get_bytes().to_vec();
+    let spans: Vec<(Option<&str>, usize, Vec<u8>)> = mixed.iter_packed().map(|(name, offset, bytes)| (name, offset, bytes.to_vec())).collect();
+
+    let mut cursor = 0;
+    for (name, offset, bytes) in &spans {
+      assert_eq!(*offset, cursor);
+      assert_eq!(&bytes[..], &packed[*offset..*offset + bytes.len()]);
+      if let Some(name) = name {
+        assert_eq!(mixed.member_layout(name), Some((*offset, bytes.len())));
+      }
+      cursor += bytes.len();
+    }
+    assert_eq!(cursor, packed.len());
+    let names: Vec<&str> = spans.iter().filter_map(|(name, _, _)| *name).collect();
+    assert_eq!(names, vec!["i", "v", "w", "m"]);
+  }
+
+  #[test]
+  fn push_constants_over_budget_are_rejected() {
+    let mut controls = Controls::new();
+    controls.insert_group(group("a", &[("m", ControlValue::from([[1.0f32; 4]; 4]))]));
+    controls.insert_group(group("b", &[("x", ControlValue::from(1.0f32))]));
+    controls.set_push_constant_groups(&["a", "b"]);
+
+    assert_eq!(controls.pack_push_constants(128).unwrap().len(), 80);
+    assert_eq!(controls.pack_push_constants(64), Err(ControlError::PushConstantsTooLarge { size: 80, max_size: 64 }));
+    controls.set_push_constant_groups(&["a", "missing"]);
+    assert_eq!(controls.pack_push_constants(128), Err(ControlError::UnknownGroup("missing".to_string())));
+  }
+
+  #[test]
+  fn fresh_groups_hand_out_packed_bytes() {
+    let mut fresh = ControlGroup::new("g");
+    fresh.values.insert("x", ControlValue::from(2.0f32));
+    assert!(!fresh.needs_update);
+    assert_eq!(fresh.get_bytes().len(), 16);
+    assert_eq!(&fresh.get_bytes()[..4], &2.0f32.to_ne_bytes());
+  }
+
+  #[cfg(feature = "testing")]
+  #[test]
+  fn out_of_range_sets_report_the_clamped_value() {
+    let mut ranged = group("g", &[("x", ControlValue::from(0.5f32))]);
+    ranged.ranges.insert("x", vec![0.0..=1.0]);
+    let mut controls = Controls::new();
+    controls.insert_group(ranged);
+    let seen = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let sink = seen.clone();
+    controls.on_change_debounced(("g", "x"), 100, move |value| sink.borrow_mut().push(value.clone()));
+
+    assert_eq!(controls.set(("g", "x"), ControlValue::from(-3.0f32)).unwrap(), ControlValue::from(0.0f32));
+    controls.simulate_set("g", "x", ControlValue::from(7.0f32)).unwrap();
+    assert_eq!(*seen.borrow(), vec![ControlValue::from(1.0f32)]);
+    assert_eq!(controls["g"]["x"], ControlValue::from(1.0f32));
+  }
+
+  #[test]
+  fn nan_floats_settle_instead_of_staying_dirty() {
+    assert!(same_float(f32::NAN, f32::NAN));
+    assert!(!same_float(f32::NAN, 0.0));
+
+    let mut stuck = group("g", &[("x", ControlValue::from(f32::NAN))]);
+    for _ in 0..3 {
+      stuck.get_bytes();
+      stuck.upload_dirty = false;
+      with_ui(|ui| { ControlGroupBuilder::new(&mut stuck).float(ui, "x", 0.0, 0.0..=1.0); });
+      assert!(!stuck.needs_update && !stuck.upload_dirty);
+    }
+  }
+
+  #[test]
+  fn vec4_groups_pack_components_in_order() {
+    let mut vectors = ControlGroup::new("g");
+    with_ui(|ui| {
+      ControlGroupBuilder::new(&mut vectors)
+        .vec4(ui, "a", [1.0, 2.0, 3.0, 4.0], 0.0..=10.0, 0.0..=10.0, 0.0..=10.0, 0.0..=10.0)
+        .vec3(ui, "b", [5.0, 6.0, 7.0], 0.0..=10.0, 0.0..=10.0, 0.0..=10.0)
+        .vec4_shared(ui, "c", [8.0, 9.0, 10.0, 0.5], 0.0..=10.0);
+    });
+    assert_eq!(vectors.member_layout("a"), Some((0, 16)));
+    assert_eq!(vectors.member_layout("b"), Some((16, 12)));
+    assert_eq!(vectors.member_layout("c"), Some((32, 16)));
+
+    let bytes = vectors.get_bytes();
+    assert_eq!(bytes.len(), 48);
+    let floats: Vec<f32> = bytes.chunks(4).map(|c| f32::from_ne_bytes(c.try_into().unwrap())).collect();
+    assert_eq!(floats, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 0.0, 8.0, 9.0, 10.0, 0.5]);
+  }
+
+  #[test]
+  fn toggled_bools_pack_as_ints() {
+    use eframe::egui::{Event, PointerButton};
+
+    let ctx = eframe::egui::Context::default();
+    let mut flags = ControlGroup::new("g");
+    let checkbox = run_frame(&ctx, Vec::new(), |ui| { ControlGroupBuilder::new(&mut flags).bool(ui, "on", false); });
+    assert_eq!(flags.member_layout("on"), Some((0, 4)));
+    assert_eq!(flags.get_bytes()[..4], 0i32.to_ne_bytes());
+    assert!(!flags.needs_update);
+
+    // click the checkbox: press on one frame, release on the next
+    let pos = checkbox.center();
+    let button = |pressed| Event::PointerButton { pos, button: PointerButton::Primary, pressed, modifiers: Modifiers::NONE };
+    run_frame(&ctx, vec![Event::PointerMoved(pos), button(true)], |ui| { ControlGroupBuilder::new(&mut flags).bool(ui, "on", false); });
+    run_frame(&ctx, vec![button(false)], |ui| { ControlGroupBuilder::new(&mut flags).bool(ui, "on", false); });
+    assert_eq!(flags["on"], ControlValue::from(true));
+    assert!(flags.needs_update);
+    assert_eq!(flags.get_bytes()[..4], 1i32.to_ne_bytes());
+  }
+
+  #[test]
+  fn curves_reject_unpackable_resolutions() {
+    let mut curves = ControlGroup::new("g");
+    with_ui(|ui| {
+      let result = ControlGroupBuilder::new(&mut curves).try_curve(ui, "falloff", &[[0.0, 1.0], [1.0, 0.0]], 6);
+      assert_eq!(result.err(), Some(ControlError::InvalidResolution { name: "falloff".to_string(), resolution: 6 }));
+    });
+    assert!(curves.values.is_empty());
+  }
 }
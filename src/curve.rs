@@ -0,0 +1,201 @@
+use eframe::egui::{pos2, vec2, Id, Pos2, Rect, Response, Sense, Shape, Stroke, Ui, Widget};
+
+/// Most samples a curve packs, 16 KiB of floats.
+pub const CURVE_MAX_RESOLUTION: usize = 4096;
+
+/// Why a curve can't pack `resolution` samples, if it can't: the samples fill whole vec4s,
+/// so it must be a nonzero multiple of 4, and at most `CURVE_MAX_RESOLUTION`.
+pub fn check_resolution(resolution: usize) -> Result<(), String> {
+  if resolution == 0 || resolution % 4 != 0 || resolution > CURVE_MAX_RESOLUTION {
+    return Err(format!("curve resolution {} is not a multiple of 4 in 4..={}", resolution, CURVE_MAX_RESOLUTION));
+  }
+  Ok(())
+}
+
+// radius of the point handles
+const HANDLE_RADIUS: f32 = 4.0;
+
+// slope of the segment starting at point `k`, flat where it has no width
+fn slope(points: &[[f32; 2]], k: usize) -> f32 {
+  let ([x0, y0], [x1, y1]) = (points[k], points[k + 1]);
+  if x1 > x0 { (y1 - y0) / (x1 - x0) } else { 0.0 }
+}
+
+// tangent at point `k`, flattened at local extrema so the curve never overshoots its points
+fn tangent(points: &[[f32; 2]], k: usize) -> f32 {
+  if k == 0 { return slope(points, 0); }
+  if k == points.len() - 1 { return slope(points, k - 1); }
+  let (before, after) = (slope(points, k - 1), slope(points, k));
+  if before * after <= 0.0 { 0.0 } else { 2.0 * before * after / (before + after) }
+}
+
+/// The value of the curve through `points` at `t`, a monotone cubic spline holding the end
+/// values past the first and last point. `points` must be sorted by x.
+pub fn sample(points: &[[f32; 2]], t: f32) -> f32 {
+  let Some(first) = points.first() else { return 0.0 };
+  let i = points.iter().position(|p| p[0] > t).unwrap_or(points.len());
+  if i == 0 { return first[1]; }
+  if i == points.len() { return points[i - 1][1]; }
+  let ([x0, y0], [x1, y1]) = (points[i - 1], points[i]);
+  let width = x1 - x0;
+  if width <= 0.0 { return y1; }
+  let (m0, m1) = (tangent(points, i - 1) * width, tangent(points, i) * width);
+  let s = (t - x0) / width;
+  let (s2, s3) = (s * s, s * s * s);
+  (2.0 * s3 - 3.0 * s2 + 1.0) * y0 + (s3 - 2.0 * s2 + s) * m0 + (3.0 * s2 - 2.0 * s3) * y1 + (s3 - s2) * m1
+}
+
+/// `points` sampled evenly from 0 to 1 into `resolution` tightly packed floats, i.e. a
+/// std140 `vec4[resolution / 4]`. The points don't need to be sorted.
+pub fn lut_bytes(points: &[[f32; 2]], resolution: usize) -> Vec<u8> {
+  let points = sorted(points);
+  let last = resolution.saturating_sub(1).max(1) as f32;
+  (0..resolution)
+    .flat_map(|i| sample(&points, i as f32 / last).to_ne_bytes())
+    .collect()
+}
+
+fn sorted(points: &[[f32; 2]]) -> Vec<[f32; 2]> {
+  let mut points = points.to_vec();
+  points.sort_by(|a, b| a[0].total_cmp(&b[0]));
+  points
+}
+
+/// Edits the points of a curve from 0 to 1 on both axes: drag a point to move it,
+/// double-click to add one and right-click a point to remove it, keeping at least two.
+/// Points are kept sorted by x except while one is dragged.
+pub struct CurveEditor<'a> {
+  points: &'a mut Vec<[f32; 2]>,
+  id: Id,
+  height: f32,
+}
+
+impl<'a> CurveEditor<'a> {
+  pub fn new(id: impl std::hash::Hash, points: &'a mut Vec<[f32; 2]>) -> Self {
+    Self { points, id: Id::new(id), height: 100.0 }
+  }
+
+  pub fn height(mut self, height: f32) -> Self {
+    self.height = height;
+    self
+  }
+}
+
+impl Widget for CurveEditor<'_> {
+  fn ui(self, ui: &mut Ui) -> Response {
+    let width = ui.available_width().max(ui.spacing().slider_width);
+    let (rect, mut response) = ui.allocate_exact_size(vec2(width, self.height), Sense::click());
+    let area = rect.shrink(HANDLE_RADIUS);
+    let to_screen = |p: [f32; 2]| pos2(area.min.x + p[0] * area.width(), area.max.y - p[1] * area.height());
+    let from_screen = |p: Pos2| [
+      ((p.x - area.min.x) / area.width()).clamp(0.0, 1.0),
+      ((area.max.y - p.y) / area.height()).clamp(0.0, 1.0),
+    ];
+    let mut changed = false;
+    // points are only reordered once none is being dragged, so handles keep their ids
+    let mut resort = false;
+
+    let (fill, frame, color) = {
+      let visuals = ui.style().interact(&response);
+      (visuals.bg_fill, visuals.bg_stroke, visuals.fg_stroke.color)
+    };
+    let painter = ui.painter_at(rect);
+    painter.rect(rect, 2.0, fill, frame);
+    let grid = Stroke::new(1.0, ui.visuals().weak_text_color().gamma_multiply(0.3));
+    for f in [0.25, 0.5, 0.75] {
+      painter.line_segment([to_screen([f, 0.0]), to_screen([f, 1.0])], grid);
+      painter.line_segment([to_screen([0.0, f]), to_screen([1.0, f])], grid);
+    }
+
+    if response.double_clicked() {
+      if let Some(pointer) = response.interact_pointer_pos() {
+        self.points.push(from_screen(pointer));
+        (changed, resort) = (true, true);
+      }
+    }
+
+    let mut removed = None;
+    for i in 0..self.points.len() {
+      let center = to_screen(self.points[i]);
+      let handle_rect = Rect::from_center_size(center, vec2(HANDLE_RADIUS, HANDLE_RADIUS) * 3.0);
+      let handle = ui.interact(handle_rect, self.id.with(("point", i)), Sense::click_and_drag());
+      if handle.dragged() {
+        let moved = from_screen(center + handle.drag_delta());
+        if moved != self.points[i] {
+          self.points[i] = moved;
+          changed = true;
+        }
+      }
+      resort |= handle.drag_stopped();
+      if handle.secondary_clicked() && self.points.len() > 2 {
+        removed = Some(i);
+      }
+      response = response | handle;
+    }
+    if let Some(i) = removed {
+      self.points.remove(i);
+      (changed, resort) = (true, true);
+    }
+    if resort {
+      self.points.sort_by(|a, b| a[0].total_cmp(&b[0]));
+    }
+
+    let sorted = sorted(&*self.points);
+    let steps = (area.width() / 2.0).max(1.0) as usize;
+    let line = (0..=steps).map(|i| {
+      let t = i as f32 / steps as f32;
+      to_screen([t, sample(&sorted, t).clamp(0.0, 1.0)])
+    }).collect();
+    painter.add(Shape::line(line, Stroke::new(2.0, color)));
+    for p in &sorted {
+      painter.circle(to_screen(*p), HANDLE_RADIUS, fill, Stroke::new(1.5, color));
+    }
+
+    if changed {
+      response.mark_changed();
+    }
+    response
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const PEAK: [[f32; 2]; 3] = [[0.2, 0.0], [0.5, 1.0], [0.8, 0.25]];
+
+  #[test]
+  fn passes_through_its_points() {
+    for [x, y] in PEAK {
+      assert_eq!(sample(&PEAK, x), y);
+    }
+  }
+
+  #[test]
+  fn holds_the_end_values() {
+    assert_eq!(sample(&PEAK, 0.0), 0.0);
+    assert_eq!(sample(&PEAK, 0.1), 0.0);
+    assert_eq!(sample(&PEAK, 0.9), 0.25);
+    assert_eq!(sample(&PEAK, 1.0), 0.25);
+    assert_eq!(sample(&[], 0.5), 0.0);
+  }
+
+  #[test]
+  fn doesnt_overshoot() {
+    // a peak, and a steep rise into a plateau
+    let rise = [[0.0, 0.0], [0.3, 0.9], [1.0, 1.0]];
+    for i in 0..=100 {
+      let t = i as f32 / 100.0;
+      assert!((0.0..=1.0).contains(&sample(&PEAK, t)), "{} at {}", sample(&PEAK, t), t);
+      assert!((0.0..=1.0).contains(&sample(&rise, t)), "{} at {}", sample(&rise, t), t);
+    }
+  }
+
+  #[test]
+  fn luts_pack_one_float_per_sample() {
+    let bytes = lut_bytes(&[[1.0, 1.0], [0.0, 0.0]], 8);
+    assert_eq!(bytes.len(), 32);
+    assert_eq!(bytes[..4], 0.0f32.to_ne_bytes());
+    assert_eq!(bytes[28..], 1.0f32.to_ne_bytes());
+  }
+}
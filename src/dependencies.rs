@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::controls::{ControlGroup, ControlValue};
+
+/// A control addressed by `(group, name)`.
+pub type ControlPath = (&'static str, &'static str);
+
+pub(crate) struct Derived {
+  pub target: ControlPath,
+  pub inputs: Vec<ControlPath>,
+  pub compute: Box<dyn Fn(&[ControlValue]) -> ControlValue>,
+  // components of the inputs the target was last computed from
+  last_inputs: Option<Vec<Vec<f64>>>,
+}
+
+/// Controls computed from other controls, kept in topological order so a single pass
+/// recomputes everything downstream of a change.
+#[derive(Default)]
+pub struct Dependencies {
+  derived: Vec<Derived>,
+}
+
+impl std::fmt::Debug for Dependencies {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_map().entries(self.derived.iter().map(|d| (d.target, &d.inputs))).finish()
+  }
+}
+
+impl Dependencies {
+  /// Adds or replaces the definition of `target`. Returns false, leaving the graph untouched,
+  /// if the definition would introduce a cycle.
+  pub(crate) fn define(&mut self, target: ControlPath, inputs: &[ControlPath], compute: Box<dyn Fn(&[ControlValue]) -> ControlValue>) -> bool {
+    let mut edges: Vec<(ControlPath, &[ControlPath])> = self.derived.iter()
+      .filter(|d| d.target != target)
+      .map(|d| (d.target, d.inputs.as_slice()))
+      .collect();
+    edges.push((target, inputs));
+
+    let Some(order) = topological_order(&edges) else { return false };
+
+    self.derived.retain(|d| d.target != target);
+    self.derived.push(Derived { target, inputs: inputs.to_vec(), compute, last_inputs: None });
+    let mut slots: Vec<Option<Derived>> = self.derived.drain(..).map(Some).collect();
+    self.derived = order.into_iter().map(|i| slots[i].take().unwrap()).collect();
+    true
+  }
+
+  // what each definition was last computed from, for rolling back a transaction
+  pub(crate) fn snapshot(&self) -> Vec<Option<Vec<Vec<f64>>>> {
+    self.derived.iter().map(|d| d.last_inputs.clone()).collect()
+  }
+
+  pub(crate) fn restore(&mut self, snapshot: Vec<Option<Vec<Vec<f64>>>>) {
+    for (derived, last_inputs) in self.derived.iter_mut().zip(snapshot) {
+      derived.last_inputs = last_inputs;
+    }
+  }
+
+  pub fn is_derived(&self, target: ControlPath) -> bool {
+    self.derived.iter().any(|d| d.target == target)
+  }
+
+  /// Names of the computed controls in `group`.
+  pub fn derived_in(&self, group: &'static str) -> BTreeSet<&'static str> {
+    self.derived.iter().filter(|d| d.target.0 == group).map(|d| d.target.1).collect()
+  }
+
+  /// Recomputes every control whose inputs changed since it was last computed. Controls
+  /// with a missing input are skipped until the input exists.
+  pub(crate) fn propagate(&mut self, groups: &mut BTreeMap<&'static str, ControlGroup>) {
+    for derived in &mut self.derived {
+      let inputs: Option<Vec<ControlValue>> = derived.inputs.iter()
+        .map(|(group, name)| groups.get(group)?.values.get(*name).cloned())
+        .collect();
+      let Some(inputs) = inputs else { continue };
+
+      let snapshot: Vec<Vec<f64>> = inputs.iter().map(|v| v.components()).collect();
+      if derived.last_inputs.as_ref() == Some(&snapshot) { continue; }
+      derived.last_inputs = Some(snapshot);
+
+      let value = (derived.compute)(&inputs);
+      let (group, name) = derived.target;
+      groups.entry(group).or_insert_with(|| ControlGroup::new(group)).commit(name, value);
+    }
+  }
+}
+
+// indices of `edges` ordered so that each target comes after the targets it reads from,
+// or `None` if the edges contain a cycle
+fn topological_order(edges: &[(ControlPath, &[ControlPath])]) -> Option<Vec<usize>> {
+  let mut order = Vec::with_capacity(edges.len());
+  let mut done = vec![false; edges.len()];
+  while order.len() < edges.len() {
+    let ready = (0..edges.len()).find(|&i| {
+      !done[i] && edges[i].1.iter().all(|input| {
+        edges.iter().enumerate().all(|(j, (target, _))| done[j] || target != input)
+      })
+    })?;
+    done[ready] = true;
+    order.push(ready);
+  }
+  Some(order)
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{controls::{ControlGroup, ControlValue, Controls}, error::ControlError};
+
+  fn int(value: &ControlValue) -> i32 {
+    match value {
+      ControlValue::Int(v, _, _) => *v,
+      _ => panic!("not an int"),
+    }
+  }
+
+  fn panel() -> Controls {
+    let mut group = ControlGroup::new("g");
+    group.commit("a", ControlValue::from(1));
+    let mut controls = Controls::new();
+    controls.insert_group(group);
+    controls
+  }
+
+  #[test]
+  fn cycles_are_rejected() {
+    let mut controls = panel();
+    controls.define(("g", "b"), &[("g", "a")], |v| v[0].clone()).unwrap();
+    let cycle = controls.define(("g", "a"), &[("g", "b")], |v| v[0].clone()).err();
+    assert_eq!(cycle, Some(ControlError::DependencyCycle("g.a".to_string())));
+    assert!(!controls.dependencies.is_derived(("g", "a")));
+  }
+
+  #[test]
+  fn diamonds_recompute_in_topological_order() {
+    let mut controls = panel();
+    // declared bottom-up, so the order has to come from the graph
+    controls.define(("g", "d"), &[("g", "b"), ("g", "c")], |v| ControlValue::from(int(&v[0]) + int(&v[1]))).unwrap();
+    controls.define(("g", "c"), &[("g", "a")], |v| ControlValue::from(int(&v[0]) * 10)).unwrap();
+    controls.define(("g", "b"), &[("g", "a")], |v| ControlValue::from(int(&v[0]) + 1)).unwrap();
+    let order: Vec<_> = controls.dependencies.derived.iter().map(|d| d.target.1).collect();
+    assert_eq!(order.last(), Some(&"d"));
+    assert_eq!(int(&controls["g"]["d"]), 12);
+
+    controls.set(("g", "a"), ControlValue::from(3)).unwrap();
+    assert_eq!(int(&controls["g"]["b"]), 4);
+    assert_eq!(int(&controls["g"]["c"]), 30);
+    assert_eq!(int(&controls["g"]["d"]), 34);
+  }
+
+  #[test]
+  fn computed_controls_render_read_only() {
+    use eframe::egui::{Context, Event, Modifiers, PointerButton, RawInput};
+
+    let mut controls = panel();
+    controls["g"].commit("on", ControlValue::from(false));
+    controls.define(("g", "off"), &[("g", "on")], |v| ControlValue::from(!matches!(v[0], ControlValue::Bool(1, ..)))).unwrap();
+    assert!(controls.dependencies.derived_in("g").contains("off"));
+
+    // click the computed checkbox: it must keep the value its input gives it
+    let ctx = Context::default();
+    let frame = |controls: &mut Controls, events: Vec<Event>| {
+      let mut drawn = eframe::egui::Rect::NOTHING;
+      let _ = ctx.run(RawInput { events, ..Default::default() }, |ctx| {
+        eframe::egui::CentralPanel::default().show(ctx, |ui| {
+          controls.group("g", |builder| builder.bool(ui, "off", false));
+          drawn = ui.min_rect();
+        });
+      });
+      drawn
+    };
+    let pos = frame(&mut controls, Vec::new()).center();
+    let button = |pressed| Event::PointerButton { pos, button: PointerButton::Primary, pressed, modifiers: Modifiers::NONE };
+    frame(&mut controls, vec![Event::PointerMoved(pos), button(true)]);
+    frame(&mut controls, vec![button(false)]);
+    assert!(matches!(controls["g"]["off"], ControlValue::Bool(1, ..)));
+  }
+}
@@ -0,0 +1,32 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlError {
+  UnknownGroup(String),
+  UnknownControl(String),
+  TypeMismatch { name: String, expected: &'static str },
+  ParseError(String),
+  DependencyCycle(String),
+  UnknownPreset(String),
+  DuplicateControl(String),
+  PushConstantsTooLarge { size: usize, max_size: usize },
+  InvalidResolution { name: String, resolution: usize },
+}
+
+impl fmt::Display for ControlError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ControlError::UnknownGroup(name) => write!(f, "unknown control group `{}`", name),
+      ControlError::UnknownControl(name) => write!(f, "unknown control `{}`", name),
+      ControlError::TypeMismatch { name, expected } => write!(f, "value `{}` is not {}", name, expected),
+      ControlError::ParseError(message) => write!(f, "failed to parse controls: {}", message),
+      ControlError::DependencyCycle(name) => write!(f, "defining `{}` would create a dependency cycle", name),
+      ControlError::UnknownPreset(name) => write!(f, "unknown preset `{}`", name),
+      ControlError::DuplicateControl(name) => write!(f, "control `{}` is declared again with a different type", name),
+      ControlError::PushConstantsTooLarge { size, max_size } => write!(f, "push constants need {} bytes but only {} fit", size, max_size),
+      ControlError::InvalidResolution { name, resolution } => write!(f, "curve `{}` can't pack {} samples, only a multiple of 4 up to {}", name, resolution, crate::curve::CURVE_MAX_RESOLUTION),
+    }
+  }
+}
+
+impl std::error::Error for ControlError {}
@@ -0,0 +1,150 @@
+/// Evaluates simple arithmetic such as `1/3`, `2*pi` or `sqrt(2) + 0.5`, for typing into
+/// drag values. Supports `+ - * / ^`, parentheses, the constants `pi`, `tau` and `e`, and
+/// the functions `sqrt`, `abs`, `sin`, `cos`, `tan`, `exp`, `ln`, `log2`, `floor`, `ceil`
+/// and `round`. Returns `None` for anything it can't parse or that isn't finite.
+pub fn eval(text: &str) -> Option<f64> {
+  let mut parser = Parser { chars: text.chars().filter(|c| !c.is_whitespace()).collect(), pos: 0 };
+  let value = parser.sum()?;
+  (parser.pos == parser.chars.len() && value.is_finite()).then_some(value)
+}
+
+struct Parser {
+  chars: Vec<char>,
+  pos: usize,
+}
+
+impl Parser {
+  fn peek(&self) -> Option<char> {
+    self.chars.get(self.pos).copied()
+  }
+
+  fn eat(&mut self, c: char) -> bool {
+    if self.peek() == Some(c) {
+      self.pos += 1;
+      true
+    } else {
+      false
+    }
+  }
+
+  // sum := product (('+' | '-') product)*
+  fn sum(&mut self) -> Option<f64> {
+    let mut value = self.product()?;
+    loop {
+      if self.eat('+') { value += self.product()?; }
+      else if self.eat('-') { value -= self.product()?; }
+      else { return Some(value); }
+    }
+  }
+
+  // product := unary (('*' | '/') unary)*
+  fn product(&mut self) -> Option<f64> {
+    let mut value = self.unary()?;
+    loop {
+      if self.eat('*') { value *= self.unary()?; }
+      else if self.eat('/') { value /= self.unary()?; }
+      else { return Some(value); }
+    }
+  }
+
+  // unary := '-' unary | power, so `-2^2` is -4
+  fn unary(&mut self) -> Option<f64> {
+    if self.eat('-') { return self.unary().map(|v| -v); }
+    if self.eat('+') { return self.unary(); }
+    self.power()
+  }
+
+  // power := atom ('^' unary)?, right associative
+  fn power(&mut self) -> Option<f64> {
+    let base = self.atom()?;
+    if self.eat('^') {
+      return self.unary().map(|exponent| base.powf(exponent));
+    }
+    Some(base)
+  }
+
+  fn atom(&mut self) -> Option<f64> {
+    if self.eat('(') {
+      let value = self.sum()?;
+      return self.eat(')').then_some(value);
+    }
+
+    let start = self.pos;
+    let c = self.peek()?;
+    if c.is_ascii_digit() || c == '.' {
+      while self.peek().map_or(false, |c| c.is_ascii_digit() || c == '.') { self.pos += 1; }
+      // exponent notation, e.g. 1e-3
+      if matches!(self.peek(), Some('e' | 'E')) {
+        let mark = self.pos;
+        self.pos += 1;
+        if !self.eat('-') { self.eat('+'); }
+        if self.peek().map_or(false, |c| c.is_ascii_digit()) {
+          while self.peek().map_or(false, |c| c.is_ascii_digit()) { self.pos += 1; }
+        } else {
+          self.pos = mark;
+        }
+      }
+      return self.chars[start..self.pos].iter().collect::<String>().parse().ok();
+    }
+
+    while self.peek().map_or(false, |c| c.is_ascii_alphanumeric()) { self.pos += 1; }
+    let name: String = self.chars[start..self.pos].iter().collect::<String>().to_lowercase();
+    match name.as_str() {
+      "pi" => return Some(std::f64::consts::PI),
+      "tau" => return Some(std::f64::consts::TAU),
+      "e" => return Some(std::f64::consts::E),
+      _ => {},
+    }
+
+    let f: fn(f64) -> f64 = match name.as_str() {
+      "sqrt" => f64::sqrt,
+      "abs" => f64::abs,
+      "sin" => f64::sin,
+      "cos" => f64::cos,
+      "tan" => f64::tan,
+      "exp" => f64::exp,
+      "ln" => f64::ln,
+      "log2" => f64::log2,
+      "floor" => f64::floor,
+      "ceil" => f64::ceil,
+      "round" => f64::round,
+      _ => return None,
+    };
+    if !self.eat('(') { return None; }
+    let argument = self.sum()?;
+    self.eat(')').then(|| f(argument))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::eval;
+
+  #[test]
+  fn precedence() {
+    assert_eq!(eval("-2^2"), Some(-4.0));
+    assert_eq!(eval("2^3^2"), Some(512.0));
+    assert_eq!(eval("1 + 2 * 3"), Some(7.0));
+  }
+
+  #[test]
+  fn functions_and_constants() {
+    assert_eq!(eval("sqrt(2)"), Some(std::f64::consts::SQRT_2));
+    assert_eq!(eval("2*pi"), Some(std::f64::consts::TAU));
+  }
+
+  #[test]
+  fn exponent_literals() {
+    assert_eq!(eval("1e-3"), Some(0.001));
+    assert_eq!(eval("2.5E2"), Some(250.0));
+  }
+
+  #[test]
+  fn rejects() {
+    assert_eq!(eval("2pi"), None);
+    assert_eq!(eval("sqrt 2"), None);
+    assert_eq!(eval("(1 + 2"), None);
+    assert_eq!(eval("1 + 2)"), None);
+    assert_eq!(eval("1/0"), None);
+  }
+}
@@ -0,0 +1,62 @@
+use eframe::egui::{pos2, vec2, Response, Sense, Stroke, Ui, Widget};
+
+/// A sphere showing a unit direction, rotated like an arcball by dragging. x points right,
+/// y up and z out of the screen; the direction is drawn dimmed while it points away.
+/// The direction stays normalized.
+pub struct DirectionGizmo<'a> {
+  direction: &'a mut [f32; 3],
+  diameter: f32,
+}
+
+impl<'a> DirectionGizmo<'a> {
+  pub fn new(direction: &'a mut [f32; 3]) -> Self {
+    Self { direction, diameter: 64.0 }
+  }
+
+  pub fn diameter(mut self, diameter: f32) -> Self {
+    self.diameter = diameter;
+    self
+  }
+}
+
+/// `v` scaled to unit length, or +z if it has none.
+pub fn normalize(v: [f32; 3]) -> [f32; 3] {
+  let length = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+  if length > 0.0 && length.is_finite() { v.map(|c| c / length) } else { [0.0, 0.0, 1.0] }
+}
+
+impl Widget for DirectionGizmo<'_> {
+  fn ui(self, ui: &mut Ui) -> Response {
+    let (rect, mut response) = ui.allocate_exact_size(vec2(self.diameter, self.diameter), Sense::drag());
+    let center = rect.center();
+    let radius = self.diameter / 2.0 - 2.0;
+
+    if response.dragged() {
+      let delta = response.drag_delta();
+      // dragging right turns the front of the sphere right, dragging down turns it down
+      let (yaw, pitch) = (delta.x / radius, delta.y / radius);
+      let [x, y, z] = *self.direction;
+      let (x, z) = (x * yaw.cos() + z * yaw.sin(), z * yaw.cos() - x * yaw.sin());
+      let (y, z) = (y * pitch.cos() - z * pitch.sin(), y * pitch.sin() + z * pitch.cos());
+      let rotated = normalize([x, y, z]);
+      if rotated != *self.direction {
+        *self.direction = rotated;
+        response.mark_changed();
+      }
+    }
+
+    let visuals = ui.style().interact(&response);
+    let painter = ui.painter_at(rect);
+    painter.circle(center, radius, visuals.bg_fill, visuals.bg_stroke);
+    let axes = Stroke::new(1.0, ui.visuals().weak_text_color().gamma_multiply(0.3));
+    painter.line_segment([pos2(center.x - radius, center.y), pos2(center.x + radius, center.y)], axes);
+    painter.line_segment([pos2(center.x, center.y - radius), pos2(center.x, center.y + radius)], axes);
+
+    let [x, y, z] = *self.direction;
+    let tip = center + vec2(x, -y) * radius;
+    let color = if z >= 0.0 { visuals.fg_stroke.color } else { visuals.fg_stroke.color.gamma_multiply(0.4) };
+    painter.line_segment([center, tip], Stroke::new(2.0, color));
+    painter.circle_filled(tip, 3.0, color);
+    response
+  }
+}
@@ -0,0 +1,138 @@
+use eframe::egui::{pos2, vec2, Color32, Id, Rect, Response, Sense, Shape, Stroke, Ui, Widget};
+
+/// Texels in the lookup table a gradient packs into, as a std140 `vec4[GRADIENT_LUT_SIZE]`.
+pub const GRADIENT_LUT_SIZE: usize = 256;
+
+// height of the preview bar and of the stop markers below it
+const BAR_HEIGHT: f32 = 20.0;
+const MARKER_SIZE: f32 = 8.0;
+
+/// The color of `stops` at `t`, interpolating linearly between neighbouring stops and
+/// holding the end colors past the first and last stop. `stops` must be sorted.
+pub fn sample(stops: &[(f32, [f32; 4])], t: f32) -> [f32; 4] {
+  let Some(first) = stops.first() else { return [0.0; 4] };
+  let i = stops.iter().position(|(p, _)| *p > t).unwrap_or(stops.len());
+  if i == 0 { return first.1; }
+  if i == stops.len() { return stops[i - 1].1; }
+  let ((p0, c0), (p1, c1)) = (stops[i - 1], stops[i]);
+  let f = if p1 > p0 { (t - p0) / (p1 - p0) } else { 0.0 };
+  std::array::from_fn(|c| c0[c] + (c1[c] - c0[c]) * f)
+}
+
+/// `stops` sampled evenly from 0 to 1 into `GRADIENT_LUT_SIZE` RGBA texels. The stops
+/// don't need to be sorted.
+pub fn lut_bytes(stops: &[(f32, [f32; 4])]) -> Vec<u8> {
+  let stops = sorted(stops);
+  (0..GRADIENT_LUT_SIZE)
+    .flat_map(|i| sample(&stops, i as f32 / (GRADIENT_LUT_SIZE - 1) as f32))
+    .flat_map(f32::to_ne_bytes)
+    .collect()
+}
+
+fn sorted(stops: &[(f32, [f32; 4])]) -> Vec<(f32, [f32; 4])> {
+  let mut stops = stops.to_vec();
+  stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+  stops
+}
+
+fn color(c: [f32; 4]) -> Color32 {
+  Color32::from_rgba_unmultiplied((c[0] * 255.0) as u8, (c[1] * 255.0) as u8, (c[2] * 255.0) as u8, (c[3] * 255.0) as u8)
+}
+
+/// Edits gradient stops: drag a marker below the bar to move its stop, click it to select
+/// it, and edit the selected stop's color next to the bar. Double-clicking the bar adds a
+/// stop there, and the remove button deletes the selected one, keeping at least two.
+/// Stops are kept sorted by position except while one is dragged.
+pub struct GradientEditor<'a> {
+  stops: &'a mut Vec<(f32, [f32; 4])>,
+  id: Id,
+}
+
+impl<'a> GradientEditor<'a> {
+  pub fn new(id: impl std::hash::Hash, stops: &'a mut Vec<(f32, [f32; 4])>) -> Self {
+    Self { stops, id: Id::new(id) }
+  }
+}
+
+impl Widget for GradientEditor<'_> {
+  fn ui(self, ui: &mut Ui) -> Response {
+    let selected_id = self.id.with("selected");
+    let mut selected = ui.memory_mut(|m| m.data.get_temp::<usize>(selected_id)).unwrap_or(0).min(self.stops.len().saturating_sub(1));
+    let mut changed = false;
+    // stops are only reordered once no marker is being dragged, so markers keep their ids
+    let mut resort = false;
+
+    let inner = ui.horizontal(|ui| {
+      let width = (ui.available_width() - 60.0).max(ui.spacing().slider_width);
+      let (rect, mut response) = ui.allocate_exact_size(vec2(width, BAR_HEIGHT + MARKER_SIZE), Sense::click());
+      let bar = Rect::from_min_size(rect.min, vec2(width, BAR_HEIGHT));
+      let painter = ui.painter_at(rect);
+
+      // the bar, as thin strips sampled across it
+      let sorted = sorted(&*self.stops);
+      let strips = (width / 2.0).max(1.0) as usize;
+      for i in 0..strips {
+        let x0 = bar.min.x + i as f32 / strips as f32 * width;
+        let x1 = bar.min.x + (i + 1) as f32 / strips as f32 * width;
+        let c = sample(&sorted, (i as f32 + 0.5) / strips as f32);
+        painter.rect_filled(Rect::from_min_max(pos2(x0, bar.min.y), pos2(x1, bar.max.y)), 0.0, color(c));
+      }
+      painter.rect_stroke(bar, 2.0, Stroke::new(1.0, ui.visuals().widgets.noninteractive.bg_stroke.color));
+
+      if response.double_clicked() {
+        if let Some(pointer) = response.interact_pointer_pos() {
+          let t = ((pointer.x - bar.min.x) / width).clamp(0.0, 1.0);
+          let c = sample(&sorted, t);
+          self.stops.push((t, c));
+          selected = self.stops.len() - 1;
+          (changed, resort) = (true, true);
+        }
+      }
+
+      for i in 0..self.stops.len() {
+        let x = bar.min.x + self.stops[i].0.clamp(0.0, 1.0) * width;
+        let marker = Rect::from_center_size(pos2(x, bar.max.y + MARKER_SIZE / 2.0), vec2(MARKER_SIZE, MARKER_SIZE));
+        let handle = ui.interact(marker, self.id.with(("stop", i)), Sense::click_and_drag());
+        if handle.clicked() || handle.drag_started() {
+          selected = i;
+        }
+        if handle.dragged() {
+          self.stops[i].0 = (self.stops[i].0 + handle.drag_delta().x / width).clamp(0.0, 1.0);
+          changed = true;
+        }
+        resort |= handle.drag_stopped();
+        let stroke = match i == selected {
+          true => Stroke::new(2.0, ui.visuals().selection.stroke.color),
+          false => Stroke::new(1.0, ui.visuals().text_color()),
+        };
+        let points = vec![pos2(x, bar.max.y), pos2(marker.min.x, marker.max.y), pos2(marker.max.x, marker.max.y)];
+        painter.add(Shape::convex_polygon(points, color(self.stops[i].1), stroke));
+        response = response | handle;
+      }
+
+      if let Some((_, c)) = self.stops.get_mut(selected) {
+        changed |= ui.color_edit_button_rgba_unmultiplied(c).changed();
+      }
+      if self.stops.len() > 2 && ui.small_button("✖").on_hover_text("remove stop").clicked() {
+        self.stops.remove(selected);
+        selected = selected.saturating_sub(1);
+        (changed, resort) = (true, true);
+      }
+      response
+    });
+
+    // keep the selection on the stop it was on
+    if resort {
+      let stop = self.stops.get(selected).copied();
+      self.stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+      selected = stop.and_then(|stop| self.stops.iter().position(|s| *s == stop)).unwrap_or(0);
+    }
+    ui.memory_mut(|m| m.data.insert_temp(selected_id, selected));
+
+    let mut response = inner.inner;
+    if changed {
+      response.mark_changed();
+    }
+    response
+  }
+}
@@ -0,0 +1,78 @@
+use eframe::egui::{Color32, Id, TextEdit, TextStyle, Ui};
+
+use crate::controls::ControlValue;
+
+// how long the field stays tinted after rejecting input, in seconds
+const ERROR_FLASH: f64 = 0.6;
+
+/// `#RRGGBB` for vec3 colors, `#RRGGBBAA` for vec4 colors and `0x...` for ints.
+pub fn format(value: &ControlValue) -> Option<String> {
+  let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+  match value {
+    ControlValue::Vec3(v, _, _) => Some(format!("#{:02X}{:02X}{:02X}", channel(v[0]), channel(v[1]), channel(v[2]))),
+    ControlValue::Vec4(v, _, _) => Some(format!("#{:02X}{:02X}{:02X}{:02X}", channel(v[0]), channel(v[1]), channel(v[2]), channel(v[3]))),
+    ControlValue::Int(v, _, _) => Some(format!("0x{:X}", *v as u32)),
+    _ => None,
+  }
+}
+
+/// Parses `text` into a value of the same type as `like`, see `format`. A vec4 also
+/// accepts `#RRGGBB` with an implied opaque alpha.
+pub fn parse(text: &str, like: &ControlValue) -> Option<ControlValue> {
+  let text = text.trim();
+  match like {
+    ControlValue::Int(_, _, _) => {
+      let digits = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X"))?;
+      u32::from_str_radix(digits, 16).ok().map(|v| ControlValue::from(v as i32))
+    },
+    ControlValue::Vec3(_, _, _) => match channels(text)?.as_slice() {
+      [r, g, b] => Some(ControlValue::from([*r, *g, *b])),
+      _ => None,
+    },
+    ControlValue::Vec4(_, _, _) => match channels(text)?.as_slice() {
+      [r, g, b] => Some(ControlValue::from([*r, *g, *b, 1.0])),
+      [r, g, b, a] => Some(ControlValue::from([*r, *g, *b, *a])),
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+fn channels(text: &str) -> Option<Vec<f32>> {
+  let digits = text.strip_prefix('#')?;
+  if !digits.is_ascii() || (digits.len() != 6 && digits.len() != 8) { return None; }
+  (0..digits.len()).step_by(2)
+    .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).ok().map(|c| c as f32 / 255.0))
+    .collect()
+}
+
+/// A text field editing `value` as hex, returning the parsed value once editing finishes.
+/// Invalid input is discarded and briefly tints the field.
+pub fn edit(ui: &mut Ui, id: Id, value: &ControlValue) -> Option<ControlValue> {
+  let formatted = format(value)?;
+  let error_id = id.with("error");
+  let now = ui.input(|i| i.time);
+  let mut text = ui.data_mut(|d| d.get_temp::<String>(id)).unwrap_or(formatted);
+  let failed = ui.data_mut(|d| d.get_temp::<f64>(error_id)).map_or(false, |t| now - t < ERROR_FLASH);
+
+  let mut field = TextEdit::singleline(&mut text).id(id.with("field")).desired_width(90.0).font(TextStyle::Monospace);
+  if failed {
+    field = field.text_color(Color32::from_rgb(0xE0, 0x40, 0x40));
+    ui.ctx().request_repaint();
+  }
+  let response = ui.add(field);
+
+  if response.lost_focus() {
+    ui.data_mut(|d| d.remove::<String>(id));
+    let parsed = parse(&text, value);
+    if parsed.is_none() {
+      ui.data_mut(|d| d.insert_temp(error_id, now));
+    }
+    parsed
+  } else {
+    if response.has_focus() {
+      ui.data_mut(|d| d.insert_temp(id, text));
+    }
+    None
+  }
+}
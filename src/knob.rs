@@ -0,0 +1,53 @@
+use eframe::egui::{vec2, Response, Sense, Stroke, Ui, Widget};
+
+// degrees a knob snaps to while shift is held
+const SNAP: f32 = 15.0;
+
+/// A rotary knob editing an angle in degrees, 0 pointing right and growing
+/// counterclockwise, kept in `0..360`. Pressing or dragging turns it to face the pointer,
+/// snapping to 15° steps while shift is held.
+pub struct Knob<'a> {
+  degrees: &'a mut f32,
+  diameter: f32,
+}
+
+impl<'a> Knob<'a> {
+  pub fn new(degrees: &'a mut f32) -> Self {
+    Self { degrees, diameter: 32.0 }
+  }
+
+  pub fn diameter(mut self, diameter: f32) -> Self {
+    self.diameter = diameter;
+    self
+  }
+}
+
+impl Widget for Knob<'_> {
+  fn ui(self, ui: &mut Ui) -> Response {
+    let (rect, mut response) = ui.allocate_exact_size(vec2(self.diameter, self.diameter), Sense::click_and_drag());
+    let center = rect.center();
+    let radius = self.diameter / 2.0 - 1.0;
+
+    if let Some(pointer) = response.interact_pointer_pos().filter(|_| response.is_pointer_button_down_on()) {
+      let offset = pointer - center;
+      if offset.length() > 1.0 {
+        let mut degrees = (-offset.y).atan2(offset.x).to_degrees().rem_euclid(360.0);
+        if ui.input(|i| i.modifiers.shift) {
+          degrees = ((degrees / SNAP).round() * SNAP).rem_euclid(360.0);
+        }
+        if degrees != *self.degrees {
+          *self.degrees = degrees;
+          response.mark_changed();
+        }
+      }
+    }
+
+    let visuals = ui.style().interact(&response);
+    let painter = ui.painter();
+    painter.circle(center, radius, visuals.bg_fill, visuals.bg_stroke);
+    let radians = self.degrees.to_radians();
+    let tip = center + vec2(radians.cos(), -radians.sin()) * (radius - 3.0);
+    painter.line_segment([center, tip], Stroke::new(2.0, visuals.fg_stroke.color));
+    response
+  }
+}
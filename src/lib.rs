@@ -1,3 +1,22 @@
 pub mod slider;
 pub mod controls;
-pub mod persist_order_map;
\ No newline at end of file
+pub mod array;
+pub mod persist_order_map;
+pub mod error;
+pub mod dependencies;
+pub mod control_ref;
+pub mod curve;
+pub mod hex;
+pub mod expr;
+pub mod gizmo;
+pub mod gradient;
+pub mod knob;
+pub mod matrix;
+pub mod pad;
+pub mod serialize;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "reflect")]
+pub mod reflect;
+#[cfg(feature = "gui")]
+pub mod app;
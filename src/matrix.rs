@@ -0,0 +1,84 @@
+/// Translation, rotation in degrees and scale of a 2D transform, as a homogeneous mat3.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Trs2 {
+  pub translation: [f32; 2],
+  pub rotation: f32,
+  pub scale: [f32; 2],
+}
+
+/// Translation, XYZ euler rotation in degrees (x applied first) and scale of a 3D
+/// transform, as a mat4.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Trs3 {
+  pub translation: [f32; 3],
+  pub rotation: [f32; 3],
+  pub scale: [f32; 3],
+}
+
+impl Default for Trs2 {
+  fn default() -> Self {
+    Self { translation: [0.0; 2], rotation: 0.0, scale: [1.0; 2] }
+  }
+}
+
+impl Default for Trs3 {
+  fn default() -> Self {
+    Self { translation: [0.0; 3], rotation: [0.0; 3], scale: [1.0; 3] }
+  }
+}
+
+impl Trs2 {
+  pub fn to_mat3(&self) -> [[f32; 3]; 3] {
+    let (sin, cos) = self.rotation.to_radians().sin_cos();
+    let [sx, sy] = self.scale;
+    let [tx, ty] = self.translation;
+    [[cos * sx, sin * sx, 0.0], [-sin * sy, cos * sy, 0.0], [tx, ty, 1.0]]
+  }
+
+  /// The transform `m` was built from, assuming it has no shear.
+  pub fn from_mat3(m: &[[f32; 3]; 3]) -> Self {
+    let sx = m[0][0].hypot(m[0][1]);
+    let sy = m[1][0].hypot(m[1][1]);
+    Self { translation: [m[2][0], m[2][1]], rotation: m[0][1].atan2(m[0][0]).to_degrees(), scale: [sx, sy] }
+  }
+}
+
+impl Trs3 {
+  pub fn to_mat4(&self) -> [[f32; 4]; 4] {
+    let [(sa, ca), (sb, cb), (sg, cg)] = self.rotation.map(|r| r.to_radians().sin_cos());
+    // rows of Rz * Ry * Rx
+    let r = [
+      [cg * cb, cg * sb * sa - sg * ca, cg * sb * ca + sg * sa],
+      [sg * cb, sg * sb * sa + cg * ca, sg * sb * ca - cg * sa],
+      [-sb, cb * sa, cb * ca],
+    ];
+    let [sx, sy, sz] = self.scale;
+    let [tx, ty, tz] = self.translation;
+    [
+      [r[0][0] * sx, r[1][0] * sx, r[2][0] * sx, 0.0],
+      [r[0][1] * sy, r[1][1] * sy, r[2][1] * sy, 0.0],
+      [r[0][2] * sz, r[1][2] * sz, r[2][2] * sz, 0.0],
+      [tx, ty, tz, 1.0],
+    ]
+  }
+
+  /// The transform `m` was built from, assuming it has no shear or projection. Near
+  /// ±90° about y, the x and z rotations can't be told apart and x takes all of it.
+  pub fn from_mat4(m: &[[f32; 4]; 4]) -> Self {
+    let length = |c: &[f32; 4]| (c[0] * c[0] + c[1] * c[1] + c[2] * c[2]).sqrt();
+    let scale = [length(&m[0]), length(&m[1]), length(&m[2])];
+    // row `i`, column `j` of the rotation
+    let r = |i: usize, j: usize| if scale[j] == 0.0 { 0.0 } else { m[j][i] / scale[j] };
+    let b = (-r(2, 0)).clamp(-1.0, 1.0).asin();
+    let (a, g) = if r(2, 0).abs() < 0.9999 {
+      (r(2, 1).atan2(r(2, 2)), r(1, 0).atan2(r(0, 0)))
+    } else {
+      ((-r(1, 2)).atan2(r(1, 1)), 0.0)
+    };
+    Self {
+      translation: [m[3][0], m[3][1], m[3][2]],
+      rotation: [a.to_degrees(), b.to_degrees(), g.to_degrees()],
+      scale,
+    }
+  }
+}
@@ -0,0 +1,67 @@
+use std::ops::RangeInclusive;
+
+use eframe::egui::{pos2, vec2, Response, Sense, Stroke, Ui, Widget};
+
+/// A 2D area setting a vec2 from where it's pressed or dragged, x growing rightwards over
+/// `x_range` and y growing upwards over `y_range`, like a trackpad.
+pub struct Pad<'a> {
+  value: &'a mut [f32; 2],
+  x_range: RangeInclusive<f32>,
+  y_range: RangeInclusive<f32>,
+  height: f32,
+}
+
+impl<'a> Pad<'a> {
+  pub fn new(value: &'a mut [f32; 2], x_range: RangeInclusive<f32>, y_range: RangeInclusive<f32>) -> Self {
+    Self { value, x_range, y_range, height: 120.0 }
+  }
+
+  pub fn height(mut self, height: f32) -> Self {
+    self.height = height;
+    self
+  }
+}
+
+// where `v` sits in `r`, from 0 at the start to 1 at the end
+fn fraction(v: f32, r: &RangeInclusive<f32>) -> f32 {
+  let span = r.end() - r.start();
+  if span == 0.0 { 0.0 } else { ((v - r.start()) / span).clamp(0.0, 1.0) }
+}
+
+impl Widget for Pad<'_> {
+  fn ui(self, ui: &mut Ui) -> Response {
+    let width = ui.available_width().max(self.height);
+    let (rect, mut response) = ui.allocate_exact_size(vec2(width, self.height), Sense::click_and_drag());
+
+    if let Some(pointer) = response.interact_pointer_pos().filter(|_| response.is_pointer_button_down_on()) {
+      let fx = ((pointer.x - rect.min.x) / rect.width()).clamp(0.0, 1.0);
+      let fy = ((rect.max.y - pointer.y) / rect.height()).clamp(0.0, 1.0);
+      let value = [
+        self.x_range.start() + fx * (self.x_range.end() - self.x_range.start()),
+        self.y_range.start() + fy * (self.y_range.end() - self.y_range.start()),
+      ];
+      if value != *self.value {
+        *self.value = value;
+        response.mark_changed();
+      }
+    }
+
+    let visuals = ui.style().interact(&response);
+    let painter = ui.painter_at(rect);
+    painter.rect(rect, 4.0, visuals.bg_fill, visuals.bg_stroke);
+    let grid = Stroke::new(1.0, ui.visuals().weak_text_color().gamma_multiply(0.3));
+    painter.line_segment([pos2(rect.center().x, rect.min.y), pos2(rect.center().x, rect.max.y)], grid);
+    painter.line_segment([pos2(rect.min.x, rect.center().y), pos2(rect.max.x, rect.center().y)], grid);
+
+    let point = pos2(
+      rect.min.x + fraction(self.value[0], &self.x_range) * rect.width(),
+      rect.max.y - fraction(self.value[1], &self.y_range) * rect.height(),
+    );
+    let crosshair = Stroke::new(1.0, visuals.fg_stroke.color.gamma_multiply(0.5));
+    painter.line_segment([pos2(point.x, rect.min.y), pos2(point.x, rect.max.y)], crosshair);
+    painter.line_segment([pos2(rect.min.x, point.y), pos2(rect.max.x, point.y)], crosshair);
+    painter.circle_filled(point, 4.0, visuals.fg_stroke.color);
+    response.on_hover_cursor(eframe::egui::CursorIcon::Crosshair)
+  }
+}
+
@@ -27,6 +27,14 @@ where
     }
   }
 
+  /// Reserves room for `capacity` keys in the order list; the map itself can't preallocate.
+  pub fn with_capacity(capacity: usize) -> Self {
+    Self {
+      inner: BTreeMap::new(),
+      order: Vec::with_capacity(capacity),
+    }
+  }
+
   pub fn insert(&mut self, key: K, value: V) {
     if !self.order.contains(&key) {
       self.order.push(key);
@@ -34,6 +42,47 @@ where
     self.inner.insert(key, value);
   }
 
+  /// Moves `key` to the position of `target` in the order. Returns false if either is missing.
+  pub fn move_to(&mut self, key: K, target: K) -> bool {
+    let from = self.order.iter().position(|k| *k == key);
+    let to = self.order.iter().position(|k| *k == target);
+    let (Some(from), Some(to)) = (from, to) else { return false };
+    let key = self.order.remove(from);
+    self.order.insert(to, key);
+    true
+  }
+
+  /// Moves the value of `old` to `new`, keeping its position in the order. Returns false if
+  /// `old` is missing or `new` already exists.
+  pub fn rename(&mut self, old: K, new: K) -> bool {
+    if self.inner.contains_key(&new) { return false; }
+    let Some(value) = self.inner.remove(&old) else { return false };
+    self.inner.insert(new, value);
+    for key in self.order.iter_mut().filter(|k| **k == old) {
+      *key = new;
+    }
+    true
+  }
+
+  /// Removes `key` from the map and the order, keeping the rest in order.
+  pub fn remove(&mut self, key: K) -> Option<V> {
+    self.order.retain(|k| *k != key);
+    self.inner.remove(&key)
+  }
+
+  pub fn clear(&mut self) {
+    self.inner.clear();
+    self.order.clear();
+  }
+
+  pub fn len(&self) -> usize {
+    self.inner.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.inner.is_empty()
+  }
+
   pub fn iter(&self) -> PersistOrderMapIterator<'_, K, V> {
     PersistOrderMapIterator {
       map: self,
@@ -56,14 +105,16 @@ where
 {
   type Item = (&'a K, &'a V);
 
+  // keys in the order but missing from the map are skipped
   fn next(&mut self) -> Option<Self::Item> {
-    if self.index < self.map.order.len() {
+    while self.index < self.map.order.len() {
       let key = &self.map.order[self.index];
       self.index += 1;
-      self.map.inner.get_key_value(key)
-    } else {
-      None
+      if let Some(entry) = self.map.inner.get_key_value(key) {
+        return Some(entry);
+      }
     }
+    None
   }
 }
 
@@ -74,13 +125,14 @@ where
   type Item = (K, V);
 
   fn next(&mut self) -> Option<Self::Item> {
-    if self.index < self.map.order.len() {
+    while self.index < self.map.order.len() {
       let key = self.map.order[self.index];
       self.index += 1;
-      self.map.inner.remove(&key).map(|value| (key, value))
-    } else {
-      None
+      if let Some(value) = self.map.inner.remove(&key) {
+        return Some((key, value));
+      }
     }
+    None
   }
 }
 
@@ -109,4 +161,22 @@ where
   fn into_iter(self) -> Self::IntoIter {
     self.iter()
   }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn removing_keeps_the_rest_in_order() {
+    let mut map = PersistOrderMap::new();
+    map.insert("c", 1);
+    map.insert("a", 2);
+    map.insert("b", 3);
+    assert_eq!(map.remove("a"), Some(2));
+    assert_eq!(map.order, vec!["c", "b"]);
+    assert_eq!(map.iter().collect::<Vec<_>>(), vec![(&"c", &1), (&"b", &3)]);
+
+    map.insert("a", 4);
+    assert_eq!(map.into_iter().collect::<Vec<_>>(), vec![("c", 1), ("b", 3), ("a", 4)]);
+  }
+}
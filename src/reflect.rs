@@ -0,0 +1,60 @@
+use std::{collections::BTreeMap, ops::RangeInclusive};
+
+use naga::{ScalarKind, TypeInner, VectorSize};
+
+use crate::{controls::{ControlGroup, ControlValue}, error::ControlError, serialize::leak};
+
+/// A `ControlGroup` generated from a shader struct, with zeroed defaults and guessed ranges.
+pub struct Reflected {
+  pub group: ControlGroup,
+  pub ranges: BTreeMap<&'static str, RangeInclusive<f32>>,
+  /// Members left out because no control type matches them, with the reason.
+  pub skipped: Vec<(String, &'static str)>,
+}
+
+/// Builds a control group mirroring the members of `struct_name` in the WGSL `source`.
+/// Members without a matching control type (matrices, arrays, textures, samplers, ...)
+/// are skipped and listed in `skipped`. Group and member names are leaked to get
+/// `'static` names, so call this once at startup rather than every frame.
+pub fn group_from_wgsl(source: &str, struct_name: &str) -> Result<Reflected, ControlError> {
+  let module = naga::front::wgsl::parse_str(source).map_err(|e| ControlError::ParseError(e.emit_to_string(source)))?;
+
+  let members = module.types.iter()
+    .find_map(|(_, ty)| match &ty.inner {
+      TypeInner::Struct { members, .. } if ty.name.as_deref() == Some(struct_name) => Some(members),
+      _ => None,
+    })
+    .ok_or_else(|| ControlError::UnknownGroup(struct_name.to_string()))?;
+
+  let mut group = ControlGroup::new(leak(struct_name));
+  let mut ranges = BTreeMap::new();
+  let mut skipped = Vec::new();
+  for member in members {
+    let Some(name) = member.name.as_deref() else { continue };
+    let (value, range) = match &module.types[member.ty].inner {
+      TypeInner::Scalar(scalar) => match scalar.kind {
+        ScalarKind::Float => (ControlValue::from(0.0f32), 0.0..=1.0),
+        ScalarKind::Sint | ScalarKind::Uint => (ControlValue::from(0i32), 0.0..=100.0),
+        ScalarKind::Bool => (ControlValue::from(false), 0.0..=1.0),
+        _ => { skipped.push((name.to_string(), "unsupported scalar")); continue; },
+      },
+      TypeInner::Vector { size, scalar } if scalar.kind == ScalarKind::Float => match size {
+        VectorSize::Bi => (ControlValue::from([0.0f32; 2]), 0.0..=1.0),
+        VectorSize::Tri => (ControlValue::from([0.0f32; 3]), 0.0..=1.0),
+        VectorSize::Quad => (ControlValue::from([0.0f32; 4]), 0.0..=1.0),
+      },
+      TypeInner::Vector { size, scalar } if matches!(scalar.kind, ScalarKind::Sint | ScalarKind::Uint) => match size {
+        VectorSize::Bi => (ControlValue::from([0i32; 2]), 0.0..=100.0),
+        VectorSize::Tri => (ControlValue::from([0i32; 3]), 0.0..=100.0),
+        VectorSize::Quad => (ControlValue::from([0i32; 4]), 0.0..=100.0),
+      },
+      _ => { skipped.push((name.to_string(), "unsupported type")); continue; },
+    };
+
+    let name = leak(name);
+    group.commit(name, value);
+    ranges.insert(name, range);
+  }
+
+  Ok(Reflected { group, ranges, skipped })
+}
@@ -0,0 +1,674 @@
+use std::collections::BTreeMap;
+
+use eframe::egui::Color32;
+use serde::{de, ser::SerializeMap, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::{controls::{Clamped, ControlGroup, ControlValue, Controls}, curve, error::ControlError};
+
+/// Upgrades a saved document from one version to the next.
+pub type Migration = fn(Value) -> Value;
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+enum Tagged {
+  Int(i32),
+  Bool(bool),
+  Float(f32),
+  Vec2([f32; 2]),
+  Vec3([f32; 3]),
+  Vec4([f32; 4]),
+  Gradient(Vec<(f32, [f32; 4])>),
+  Mat3([[f32; 3]; 3]),
+  Mat4([[f32; 4]; 4]),
+  IVec2([i32; 2]),
+  IVec3([i32; 3]),
+  IVec4([i32; 4]),
+  Curve { points: Vec<[f32; 2]>, resolution: usize },
+}
+
+impl From<&ControlValue> for Tagged {
+  fn from(value: &ControlValue) -> Self {
+    match value {
+      ControlValue::Int(v, _, _) => Tagged::Int(*v),
+      ControlValue::Bool(v, _, _) => Tagged::Bool(*v != 0),
+      ControlValue::Float(v, _, _) => Tagged::Float(*v),
+      ControlValue::Vec2(v, _, _) => Tagged::Vec2(*v),
+      ControlValue::Vec3(v, _, _) => Tagged::Vec3(*v),
+      ControlValue::Vec4(v, _, _) => Tagged::Vec4(*v),
+      ControlValue::Gradient(stops, _, _) => Tagged::Gradient(stops.clone()),
+      ControlValue::Mat3(m, _, _) => Tagged::Mat3(*m),
+      ControlValue::Mat4(m, _, _) => Tagged::Mat4(*m),
+      ControlValue::IVec2(v, _, _) => Tagged::IVec2(*v),
+      ControlValue::IVec3(v, _, _) => Tagged::IVec3(*v),
+      ControlValue::IVec4(v, _, _) => Tagged::IVec4(*v),
+      ControlValue::Curve(points, bytes, _) => Tagged::Curve { points: points.clone(), resolution: bytes.len() / 4 },
+    }
+  }
+}
+
+impl From<Tagged> for ControlValue {
+  fn from(value: Tagged) -> Self {
+    match value {
+      Tagged::Int(v) => ControlValue::from(v),
+      Tagged::Bool(v) => ControlValue::from(v),
+      Tagged::Float(v) => ControlValue::from(v),
+      Tagged::Vec2(v) => ControlValue::from(v),
+      Tagged::Vec3(v) => ControlValue::from(v),
+      Tagged::Vec4(v) => ControlValue::from(v),
+      Tagged::Gradient(stops) => ControlValue::from(stops),
+      Tagged::Mat3(m) => ControlValue::from(m),
+      Tagged::Mat4(m) => ControlValue::from(m),
+      Tagged::IVec2(v) => ControlValue::from(v),
+      Tagged::IVec3(v) => ControlValue::from(v),
+      Tagged::IVec4(v) => ControlValue::from(v),
+      Tagged::Curve { points, resolution } => ControlValue::from((points, resolution)),
+    }
+  }
+}
+
+impl Serialize for ControlValue {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    Tagged::from(self).serialize(serializer)
+  }
+}
+
+impl<'de> Deserialize<'de> for ControlValue {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let tagged = Tagged::deserialize(deserializer)?;
+    // the resolution sizes the packed samples, so don't trust it to allocate
+    if let Tagged::Curve { resolution, .. } = &tagged {
+      curve::check_resolution(*resolution).map_err(de::Error::custom)?;
+    }
+    Ok(ControlValue::from(tagged))
+  }
+}
+
+struct Group<'a>(&'a ControlGroup);
+
+impl Serialize for Group<'_> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(None)?;
+    for (name, value) in &self.0.values {
+      map.serialize_entry(name, value)?;
+    }
+    map.end()
+  }
+}
+
+struct Groups<'a>(&'a BTreeMap<&'static str, ControlGroup>);
+
+impl Serialize for Groups<'_> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(self.0.len()))?;
+    for (name, group) in self.0 {
+      map.serialize_entry(name, &Group(group))?;
+    }
+    map.end()
+  }
+}
+
+struct Tints<'a>(&'a BTreeMap<&'static str, ControlGroup>);
+
+impl Serialize for Tints<'_> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(None)?;
+    for (name, group) in self.0 {
+      if let Some(tint) = group.tint {
+        map.serialize_entry(name, &tint.to_hex())?;
+      }
+      for (control, tint) in &group.tints {
+        map.serialize_entry(&format!("{}.{}", name, control), &tint.to_hex())?;
+      }
+    }
+    map.end()
+  }
+}
+
+/// Written as `{ "version": n, "groups": { group: { control: { "type", "value" } } } }`,
+/// with controls in pack order. Tints, if any, follow as `"tints": { "group" or
+/// "group.control": "#rrggbbaa" }`.
+impl Serialize for Controls {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let tinted = self.values.values().any(|g| g.tint.is_some() || !g.tints.is_empty());
+    let mut map = serializer.serialize_map(Some(if tinted { 3 } else { 2 }))?;
+    map.serialize_entry("version", &self.version)?;
+    map.serialize_entry("groups", &Groups(&self.values))?;
+    if tinted {
+      map.serialize_entry("tints", &Tints(&self.values))?;
+    }
+    map.end()
+  }
+}
+
+/// Builds a new `Controls` holding every group and control of the document. Names are
+/// leaked to get `'static` names and no migrations are registered, so only documents at
+/// the default version load; prefer `Controls::from_json` on declared controls.
+impl<'de> Deserialize<'de> for Controls {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let document = Value::deserialize(deserializer)?;
+    let mut controls = Controls::new();
+    controls.apply_document(document, true).map_err(de::Error::custom)?;
+    Ok(controls)
+  }
+}
+
+impl Controls {
+  /// The version written into saved documents. Bump it whenever groups or controls are
+  /// renamed, and register a migration from the previous version.
+  pub fn set_version(&mut self, version: u32) -> &mut Self {
+    self.version = version;
+    self
+  }
+
+  /// Registers how to upgrade a document saved at `from_version` to `from_version + 1`.
+  pub fn register_migration(&mut self, from_version: u32, migration: Migration) -> &mut Self {
+    self.migrations.insert(from_version, migration);
+    self
+  }
+
+  /// Runs the registered migrations to bring a document saved at `from_version` up to the
+  /// current version.
+  pub fn migrate(&self, from_version: u32, json: Value) -> Result<Value, ControlError> {
+    if from_version > self.version {
+      return Err(ControlError::ParseError(format!("document version {} is newer than {}", from_version, self.version)));
+    }
+
+    let mut json = json;
+    for version in from_version..self.version {
+      let migration = self.migrations.get(&version)
+        .ok_or_else(|| ControlError::ParseError(format!("no migration from version {}", version)))?;
+      json = migration(json);
+    }
+    Ok(json)
+  }
+
+  /// Loads values saved with `serde_json` onto the declared controls, migrating older
+  /// documents first. A document without a `version`, e.g. written by hand, is taken to be
+  /// at the current version. Controls that don't exist or changed type are left alone, and
+  /// values outside their control's range are clamped into it and reported back.
+  pub fn from_json(&mut self, json: &str) -> Result<Vec<Clamped>, ControlError> {
+    let document = serde_json::from_str(json).map_err(|e| ControlError::ParseError(e.to_string()))?;
+    self.apply_document(document, false)
+  }
+
+  /// Writes every group as a `[groups.<group>.<control>]` table holding `type` and `value`,
+  /// in the same document model as the JSON format.
+  #[cfg(feature = "toml")]
+  pub fn to_toml(&self) -> Result<String, ControlError> {
+    toml::to_string_pretty(self).map_err(|e| ControlError::ParseError(e.to_string()))
+  }
+
+  /// Like `from_json`, for documents written by `to_toml` or edited by hand. Controls are
+  /// read in the order their tables appear in the file.
+  #[cfg(feature = "toml")]
+  pub fn from_toml(&mut self, source: &str) -> Result<Vec<Clamped>, ControlError> {
+    let document = toml::from_str::<Value>(source).map_err(|e| ControlError::ParseError(e.to_string()))?;
+    self.apply_document(document, false)
+  }
+
+  // applies a saved document, creating missing groups and controls if `create` is set
+  pub(crate) fn apply_document(&mut self, document: Value, create: bool) -> Result<Vec<Clamped>, ControlError> {
+    let version = document.get("version").and_then(Value::as_u64).map_or(self.version, |v| v as u32);
+    let document = self.migrate(version, document)?;
+    let groups = document.get("groups").and_then(Value::as_object)
+      .ok_or_else(|| ControlError::ParseError("missing `groups`".to_string()))?;
+
+    let mut clamped = Vec::new();
+    for (group_name, controls) in groups {
+      let controls = controls.as_object()
+        .ok_or_else(|| ControlError::ParseError(format!("group `{}` is not an object", group_name)))?;
+      if create && !self.values.contains_key(group_name.as_str()) {
+        let name = leak(group_name);
+        self.values.insert(name, ControlGroup::new(name));
+      }
+      let Some(group) = self.values.get_mut(group_name.as_str()) else { continue };
+
+      for (name, value) in controls {
+        let value = ControlValue::deserialize(value).map_err(|e| ControlError::ParseError(format!("{}.{}: {}", group_name, name, e)))?;
+        load_value(group, name, value, create, &mut clamped);
+      }
+    }
+
+    let tints = document.get("tints").and_then(Value::as_object).into_iter().flatten();
+    for (path, tint) in tints {
+      let tint = tint.as_str().and_then(|hex| Color32::from_hex(hex).ok())
+        .ok_or_else(|| ControlError::ParseError(format!("tint of `{}` is not a hex color", path)))?;
+      let (group_name, control) = match path.split_once('.') {
+        Some((group, control)) => (group, Some(control)),
+        None => (path.as_str(), None),
+      };
+      let Some(group) = self.values.get_mut(group_name) else { continue };
+      match control {
+        None => group.set_tint(tint),
+        Some(control) => if let Some((&key, _)) = group.values.inner.get_key_value(control) {
+          group.set_control_tint(key, tint);
+        },
+      }
+    }
+
+    self.recompute();
+    Ok(clamped)
+  }
+}
+
+// commits a loaded value onto a declared control of the same type, clamped into its range
+fn load_value(group: &mut ControlGroup, name: &str, value: ControlValue, create: bool, clamped: &mut Vec<Clamped>) {
+  match group.values.inner.get_key_value(name) {
+    Some((&key, current)) => {
+      if std::mem::discriminant(current) != std::mem::discriminant(&value) { return; }
+      let in_range = group.ranges.get(key).and_then(|ranges| value.clamp_components(ranges));
+      if let Some(in_range) = in_range {
+        clamped.push(Clamped { group: group.name, name: key, loaded: value, clamped: in_range.clone() });
+        group.commit(key, in_range);
+      } else {
+        group.commit(key, value);
+      }
+    },
+    None if create => group.commit(leak(name), value),
+    None => {},
+  }
+}
+
+// -------------------- plain json -------------------- //
+impl Controls {
+  /// The values of every control as pretty-printed JSON keyed by group then control, e.g.
+  /// `{"lighting":{"intensity":0.5,"dir":[0.0,1.0,0.0]}}`. Types aren't written, `load_json`
+  /// takes them from the declared controls, and neither are packed bytes.
+  pub fn to_json(&self) -> String {
+    let groups: serde_json::Map<String, Value> = self.values.iter().map(|(group_name, group)| {
+      let controls = group.values.iter().map(|(name, value)| {
+        let mut tagged = serde_json::to_value(value).expect("control values always serialize");
+        (name.to_string(), tagged["value"].take())
+      }).collect();
+      (group_name.to_string(), Value::Object(controls))
+    }).collect();
+    serde_json::to_string_pretty(&groups).expect("control values always serialize")
+  }
+
+  /// Loads JSON written by `to_json` onto the declared controls, reading each value as the
+  /// type its control holds. Groups and controls that don't exist are skipped, and values
+  /// outside a control's range are clamped into it. Nothing is loaded if any value has the
+  /// wrong type. Loaded controls are committed, so they repack on their next `get_bytes`.
+  pub fn load_json(&mut self, json: &str) -> Result<(), ControlError> {
+    let document: Value = serde_json::from_str(json).map_err(|e| ControlError::ParseError(e.to_string()))?;
+    let groups = document.as_object().ok_or_else(|| ControlError::ParseError("not an object of groups".to_string()))?;
+
+    let mut loaded = Vec::new();
+    for (group_name, controls) in groups {
+      let Some(group) = self.values.get(group_name.as_str()) else { continue };
+      let controls = controls.as_object()
+        .ok_or_else(|| ControlError::ParseError(format!("group `{}` is not an object", group_name)))?;
+      for (name, value) in controls {
+        let Some(current) = group.values.inner.get(name.as_str()) else { continue };
+        let tagged = serde_json::json!({ "type": current.type_name(), "value": value });
+        let value = ControlValue::deserialize(tagged)
+          .map_err(|_| ControlError::TypeMismatch { name: format!("{}.{}", group_name, name), expected: current.type_name() })?;
+        loaded.push((group.name, name, value));
+      }
+    }
+
+    for (group_name, name, value) in loaded {
+      load_value(self.values.get_mut(group_name).unwrap(), name, value, false, &mut Vec::new());
+    }
+    self.recompute();
+    Ok(())
+  }
+}
+
+// -------------------- binary -------------------- //
+const BYTES_MAGIC: &[u8; 4] = b"RUIS";
+const BYTES_VERSION: u8 = 1;
+
+impl Controls {
+  /// Saves every value in a compact binary form, much smaller and faster to load than
+  /// JSON. Unlike `export_bin_bytes`, this keeps names and types rather than the GPU
+  /// layout, so it loads back with `from_bytes` even after controls are added or moved.
+  ///
+  /// All integers are little endian:
+  /// ```text
+  /// "RUIS", format version (u8), document version (u32)
+  /// name count (u32), then per name: length (u16), name (UTF-8)
+  /// group count (u32), then per group: name index (u32), control count (u32),
+  ///   then per control: name index (u32), type tag (u8), value
+  /// ```
+  /// Tags are 0 int (i32), 1 bool (u8), 2 float, 3 vec2, 4 vec3 and 5 vec4 (f32s), 6
+  /// gradient: stop count (u32), then per stop its position and rgba (f32s), and 7 mat3 and
+  /// 8 mat4 (f32s, column-major, unpadded), 9 ivec2, 10 ivec3 and 11 ivec4 (i32s), and 12
+  /// curve: resolution and point count (u32s), then per point its x and y (f32s).
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut names: Vec<&str> = Vec::new();
+    let mut intern = |name: &'static str| match names.iter().position(|n| *n == name) {
+      Some(i) => i as u32,
+      None => { names.push(name); names.len() as u32 - 1 },
+    };
+
+    let mut body = Vec::new();
+    body.extend_from_slice(&(self.values.len() as u32).to_le_bytes());
+    for (group_name, group) in &self.values {
+      body.extend_from_slice(&intern(group_name).to_le_bytes());
+      body.extend_from_slice(&(group.values.len() as u32).to_le_bytes());
+      for (name, value) in &group.values {
+        body.extend_from_slice(&intern(name).to_le_bytes());
+        let floats: &[f32] = match value {
+          ControlValue::Int(v, _, _) => { body.push(0); body.extend_from_slice(&v.to_le_bytes()); &[] },
+          ControlValue::Bool(v, _, _) => { body.push(1); body.push((*v != 0) as u8); &[] },
+          ControlValue::Float(v, _, _) => { body.push(2); std::slice::from_ref(v) },
+          ControlValue::Vec2(v, _, _) => { body.push(3); v },
+          ControlValue::Vec3(v, _, _) => { body.push(4); v },
+          ControlValue::Vec4(v, _, _) => { body.push(5); v },
+          ControlValue::Gradient(stops, _, _) => {
+            body.push(6);
+            body.extend_from_slice(&(stops.len() as u32).to_le_bytes());
+            for (position, color) in stops {
+              body.extend_from_slice(&position.to_le_bytes());
+              color.iter().for_each(|c| body.extend_from_slice(&c.to_le_bytes()));
+            }
+            &[]
+          },
+          ControlValue::Mat3(m, _, _) => {
+            body.push(7);
+            m.iter().flatten().for_each(|c| body.extend_from_slice(&c.to_le_bytes()));
+            &[]
+          },
+          ControlValue::Mat4(m, _, _) => {
+            body.push(8);
+            m.iter().flatten().for_each(|c| body.extend_from_slice(&c.to_le_bytes()));
+            &[]
+          },
+          ControlValue::IVec2(v, _, _) => { body.push(9); v.iter().for_each(|c| body.extend_from_slice(&c.to_le_bytes())); &[] },
+          ControlValue::IVec3(v, _, _) => { body.push(10); v.iter().for_each(|c| body.extend_from_slice(&c.to_le_bytes())); &[] },
+          ControlValue::IVec4(v, _, _) => { body.push(11); v.iter().for_each(|c| body.extend_from_slice(&c.to_le_bytes())); &[] },
+          ControlValue::Curve(points, bytes, _) => {
+            body.push(12);
+            body.extend_from_slice(&((bytes.len() / 4) as u32).to_le_bytes());
+            body.extend_from_slice(&(points.len() as u32).to_le_bytes());
+            points.iter().flatten().for_each(|c| body.extend_from_slice(&c.to_le_bytes()));
+            &[]
+          },
+        };
+        for c in floats {
+          body.extend_from_slice(&c.to_le_bytes());
+        }
+      }
+    }
+
+    let mut bytes = Vec::with_capacity(body.len() + 13 + names.iter().map(|n| n.len() + 2).sum::<usize>());
+    bytes.extend_from_slice(BYTES_MAGIC);
+    bytes.push(BYTES_VERSION);
+    bytes.extend_from_slice(&self.version.to_le_bytes());
+    bytes.extend_from_slice(&(names.len() as u32).to_le_bytes());
+    for name in names {
+      bytes.extend_from_slice(&(name.len() as u16).to_le_bytes());
+      bytes.extend_from_slice(name.as_bytes());
+    }
+    bytes.extend_from_slice(&body);
+    bytes
+  }
+
+  /// Loads values written by `to_bytes` onto the declared controls, like `from_json`.
+  /// Data saved at an older document version goes through the registered migrations, as
+  /// a JSON document. Fails on data written by a newer format or document version, or that
+  /// is cut short.
+  pub fn from_bytes(&mut self, bytes: &[u8]) -> Result<Vec<Clamped>, ControlError> {
+    let mut reader = Reader { bytes, pos: 0 };
+    if reader.take(4)? != BYTES_MAGIC {
+      return Err(ControlError::ParseError("not a rustui save".to_string()));
+    }
+    let format = reader.u8()?;
+    if format > BYTES_VERSION {
+      return Err(ControlError::ParseError(format!("binary format {} is newer than {}", format, BYTES_VERSION)));
+    }
+    let version = reader.u32()?;
+    if version > self.version {
+      return Err(ControlError::ParseError(format!("document version {} is newer than {}", version, self.version)));
+    }
+
+    let names = (0..reader.u32()?).map(|_| {
+      let len = reader.u16()? as usize;
+      std::str::from_utf8(reader.take(len)?).map_err(|e| ControlError::ParseError(e.to_string()))
+    }).collect::<Result<Vec<&str>, ControlError>>()?;
+    let name = |index: u32| names.get(index as usize).copied()
+      .ok_or_else(|| ControlError::ParseError(format!("name index {} out of bounds", index)));
+
+    let mut loaded = Vec::new();
+    for _ in 0..reader.u32()? {
+      let group_name = name(reader.u32()?)?;
+      let count = reader.u32()?;
+      for _ in 0..count {
+        let control = name(reader.u32()?)?;
+        let value = match reader.u8()? {
+          0 => ControlValue::from(i32::from_le_bytes(reader.array()?)),
+          1 => ControlValue::from(reader.u8()? != 0),
+          2 => ControlValue::from(reader.f32s::<1>()?[0]),
+          3 => ControlValue::from(reader.f32s::<2>()?),
+          4 => ControlValue::from(reader.f32s::<3>()?),
+          5 => ControlValue::from(reader.f32s::<4>()?),
+          6 => {
+            let len = reader.u32()?;
+            let stops = (0..len).map(|_| Ok((reader.f32s::<1>()?[0], reader.f32s::<4>()?))).collect::<Result<Vec<_>, ControlError>>()?;
+            ControlValue::from(stops)
+          },
+          7 => {
+            let [a, b, c] = [reader.f32s::<3>()?, reader.f32s::<3>()?, reader.f32s::<3>()?];
+            ControlValue::from([a, b, c])
+          },
+          8 => {
+            let [a, b, c, d] = [reader.f32s::<4>()?, reader.f32s::<4>()?, reader.f32s::<4>()?, reader.f32s::<4>()?];
+            ControlValue::from([a, b, c, d])
+          },
+          9 => ControlValue::from(reader.i32s::<2>()?),
+          10 => ControlValue::from(reader.i32s::<3>()?),
+          11 => ControlValue::from(reader.i32s::<4>()?),
+          12 => {
+            let resolution = reader.u32()? as usize;
+            curve::check_resolution(resolution).map_err(|e| ControlError::ParseError(format!("{}.{}: {}", group_name, control, e)))?;
+            let len = reader.u32()?;
+            let points = (0..len).map(|_| reader.f32s::<2>()).collect::<Result<Vec<_>, ControlError>>()?;
+            ControlValue::from((points, resolution))
+          },
+          tag => return Err(ControlError::ParseError(format!("{}.{}: unknown type tag {}", group_name, control, tag))),
+        };
+        loaded.push((group_name, control, value));
+      }
+    }
+
+    // migrations work on JSON documents, so older data takes the same path as `from_json`
+    if version < self.version {
+      let mut groups = serde_json::Map::new();
+      for (group_name, control, value) in &loaded {
+        let group = groups.entry(*group_name).or_insert_with(|| Value::Object(serde_json::Map::new()));
+        let value = serde_json::to_value(value).map_err(|e| ControlError::ParseError(e.to_string()))?;
+        group.as_object_mut().unwrap().insert(control.to_string(), value);
+      }
+      let document = serde_json::json!({ "version": version, "groups": groups });
+      return self.apply_document(document, false);
+    }
+
+    let mut clamped = Vec::new();
+    for (group_name, control, value) in loaded {
+      if let Some(group) = self.values.get_mut(group_name) {
+        load_value(group, control, value, false, &mut clamped);
+      }
+    }
+    self.recompute();
+    Ok(clamped)
+  }
+}
+
+struct Reader<'a> {
+  bytes: &'a [u8],
+  pos: usize,
+}
+
+impl<'a> Reader<'a> {
+  fn take(&mut self, len: usize) -> Result<&'a [u8], ControlError> {
+    let bytes = self.bytes.get(self.pos..self.pos + len)
+      .ok_or_else(|| ControlError::ParseError("unexpected end of data".to_string()))?;
+    self.pos += len;
+    Ok(bytes)
+  }
+
+  fn array<const N: usize>(&mut self) -> Result<[u8; N], ControlError> {
+    Ok(self.take(N)?.try_into().unwrap())
+  }
+
+  fn u8(&mut self) -> Result<u8, ControlError> {
+    Ok(self.take(1)?[0])
+  }
+
+  fn u16(&mut self) -> Result<u16, ControlError> {
+    self.array().map(u16::from_le_bytes)
+  }
+
+  fn u32(&mut self) -> Result<u32, ControlError> {
+    self.array().map(u32::from_le_bytes)
+  }
+
+  fn f32s<const N: usize>(&mut self) -> Result<[f32; N], ControlError> {
+    let mut values = [0.0; N];
+    for v in &mut values {
+      *v = f32::from_le_bytes(self.array()?);
+    }
+    Ok(values)
+  }
+
+  fn i32s<const N: usize>(&mut self) -> Result<[i32; N], ControlError> {
+    let mut values = [0; N];
+    for v in &mut values {
+      *v = i32::from_le_bytes(self.array()?);
+    }
+    Ok(values)
+  }
+}
+
+pub(crate) fn leak(s: &str) -> &'static str {
+  Box::leak(s.to_string().into_boxed_str())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // a RUIS document holding the single control `g.c` with type `tag`, followed by `value`
+  fn document(tag: u8, value: &[u8]) -> Vec<u8> {
+    let mut bytes = b"RUIS".to_vec();
+    bytes.push(BYTES_VERSION);
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&2u32.to_le_bytes());
+    for name in ["g", "c"] {
+      bytes.extend_from_slice(&(name.len() as u16).to_le_bytes());
+      bytes.extend_from_slice(name.as_bytes());
+    }
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.push(tag);
+    bytes.extend_from_slice(value);
+    bytes
+  }
+
+  fn with_curve() -> Controls {
+    let mut group = ControlGroup::new("g");
+    group.commit("c", ControlValue::from((vec![[0.0, 0.0], [1.0, 1.0]], 16)));
+    let mut controls = Controls::new();
+    controls.insert_group(group);
+    controls
+  }
+
+  #[test]
+  fn curve_resolution_is_checked_before_allocating() {
+    for resolution in [0, 6, u32::MAX] {
+      let bytes = document(12, &resolution.to_le_bytes());
+      assert!(matches!(with_curve().from_bytes(&bytes), Err(ControlError::ParseError(_))));
+
+      let json = format!(r#"{{ "version": 1, "groups": {{ "g": {{ "c": {{ "type": "curve", "value": {{ "points": [], "resolution": {} }} }} }} }} }}"#, resolution);
+      assert!(matches!(with_curve().from_json(&json), Err(ControlError::ParseError(_))));
+    }
+  }
+
+  fn declared(values: &[(&'static str, ControlValue)]) -> Controls {
+    let mut group = ControlGroup::new("g");
+    for (name, value) in values {
+      group.commit(*name, value.clone());
+    }
+    let mut controls = Controls::new();
+    controls.insert_group(group);
+    controls
+  }
+
+  #[test]
+  fn bytes_round_trip() {
+    let values = [
+      ("i", ControlValue::from(-7)),
+      ("b", ControlValue::from(true)),
+      ("f", ControlValue::from(0.25f32)),
+      ("v", ControlValue::from([1.0f32, -2.0, 3.5])),
+      ("g", ControlValue::from(vec![(0.0, [0.0, 0.0, 0.0, 1.0]), (1.0, [1.0, 0.5, 0.25, 1.0])])),
+      ("m", ControlValue::from([[1.0f32, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]])),
+      ("n", ControlValue::from([3, 4])),
+      ("c", ControlValue::from((vec![[0.0, 1.0], [0.5, 0.2], [1.0, 0.0]], 8))),
+    ];
+    let saved = declared(&values);
+    let mut loaded = declared(&values);
+    loaded["g"].commit("i", ControlValue::from(0));
+    loaded["g"].commit("f", ControlValue::from(0.0f32));
+    loaded["g"].commit("c", ControlValue::from((vec![[0.0, 0.0], [1.0, 1.0]], 8)));
+
+    assert!(loaded.from_bytes(&saved.to_bytes()).unwrap().is_empty());
+    for (name, value) in &values {
+      assert_eq!(loaded["g"][*name], *value, "{}", name);
+    }
+  }
+
+  fn rename_c_to_d(mut document: Value) -> Value {
+    let group = document["groups"]["g"].as_object_mut().unwrap();
+    if let Some(value) = group.remove("c") {
+      group.insert("d".to_string(), value);
+    }
+    document
+  }
+
+  #[test]
+  fn older_bytes_are_migrated() {
+    let saved = declared(&[("c", ControlValue::from(5))]);
+    let bytes = saved.to_bytes();
+
+    let mut current = declared(&[("d", ControlValue::from(0))]);
+    current.set_version(2).register_migration(1, rename_c_to_d);
+    current.from_bytes(&bytes).unwrap();
+    assert_eq!(current["g"]["d"], ControlValue::from(5));
+  }
+
+  #[test]
+  fn unversioned_documents_load_at_the_current_version() {
+    let mut controls = declared(&[("c", ControlValue::from(0))]);
+    controls.set_version(3);
+    controls.from_json(r#"{ "groups": { "g": { "c": { "type": "int", "value": 4 } } } }"#).unwrap();
+    assert_eq!(controls["g"]["c"], ControlValue::from(4));
+  }
+
+  #[test]
+  fn plain_json_is_keyed_by_group_then_control() {
+    let saved = declared(&[("intensity", ControlValue::from(0.5f32)), ("dir", ControlValue::from([0.0f32, 1.0, 0.0])), ("on", ControlValue::from(true))]);
+    let json: Value = serde_json::from_str(&saved.to_json()).unwrap();
+    assert_eq!(json, serde_json::json!({ "g": { "intensity": 0.5, "dir": [0.0, 1.0, 0.0], "on": true } }));
+
+    let mut loaded = declared(&[("intensity", ControlValue::from(0.0f32)), ("dir", ControlValue::from([0.0f32; 3])), ("on", ControlValue::from(false))]);
+    loaded["g"].get_bytes();
+    loaded.load_json(&saved.to_json()).unwrap();
+    assert!(saved.diff(&loaded).is_empty());
+    assert!(loaded["g"].needs_update);
+  }
+
+  #[test]
+  fn plain_json_with_a_wrong_type_loads_nothing() {
+    let mut controls = declared(&[("a", ControlValue::from(1.0f32)), ("b", ControlValue::from(2))]);
+    let result = controls.load_json(r#"{ "g": { "a": 5.0, "b": [1, 2], "missing": 3 }, "other": {} }"#);
+    assert!(matches!(result, Err(ControlError::TypeMismatch { .. })));
+    assert_eq!(controls["g"]["a"], ControlValue::from(1.0f32));
+    assert!(controls.load_json(r#"{ "g": { "a": 5.0, "missing": 3 }, "other": {} }"#).is_ok());
+    assert_eq!(controls["g"]["a"], ControlValue::from(5.0f32));
+  }
+}
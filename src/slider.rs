@@ -1,52 +1,435 @@
-use eframe::egui::{emath::Numeric, vec2, Color32, DragValue, Layout, Rect, Sense, Widget};
+use std::collections::VecDeque;
+
+use eframe::egui::{emath::Numeric, pos2, vec2, Align2, Color32, ComboBox, DragValue, FontId, Key, Label, Layout, Modifiers, Rect, Sense, Shape, Stroke, TextEdit, Widget};
+
+use crate::expr;
+
+/// Sizes of the parts of a `Slider`. The default is the stock look.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SliderStyle {
+  pub corner_radius: f32,
+  pub track_height: f32,
+  pub cursor_width: f32,
+  pub title_width: f32,
+  /// Lays the slider out as a property grid row: the title is cut to `title_width`, showing
+  /// the full text on hover, and the value box has a fixed width, so tracks line up.
+  pub label_column: bool,
+}
+
+impl Default for SliderStyle {
+  fn default() -> Self {
+    Self {
+      corner_radius: 4.0,
+      track_height: 20.0,
+      cursor_width: 4.0,
+      title_width: 100.0,
+      label_column: false,
+    }
+  }
+}
+
+/// How values are spread along a `Slider`'s track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SliderScale {
+  #[default]
+  Linear,
+  /// Equal ratios take equal distances, for ranges like `0.001..=1000.0`. Needs a range
+  /// above zero.
+  Logarithmic,
+}
 
 pub struct Slider<'a, T> {
   pub title: Option<&'a str>,
   pub range: std::ops::RangeInclusive<T>,
   pub get_set_value: Box<dyn 'a + FnMut(Option<T>) -> T>,
+  pub click_jumps: bool,
+  pub drag_speed: f64,
+  pub speed_modifier: (Modifiers, f64),
+  pub selected: Option<&'a mut bool>,
+  pub history: usize,
+  pub style: SliderStyle,
+  pub autofit_range: bool,
+  pub transform: Option<(Box<dyn 'a + Fn(f64) -> f64>, Box<dyn 'a + Fn(f64) -> f64>)>,
+  pub indeterminate: bool,
+  pub target: Option<T>,
+  pub tint: Option<Color32>,
+  pub show_delta: bool,
+  pub segments: Vec<(f32, f64)>,
+  pub unified: bool,
+  pub off_value: Option<T>,
+  pub commit_on_release: bool,
+  pub reversed: bool,
+  pub quantize_input: bool,
+  pub gauge: Option<T>,
+  pub range_presets: Vec<(&'a str, std::ops::RangeInclusive<T>)>,
+  pub readout: Option<(Box<dyn 'a + Fn(f64) -> String>, Box<dyn 'a + Fn(&str) -> Option<f64>>)>,
+  pub scrub_strip: bool,
+  pub scale: SliderScale,
 }
 
+// extra points on each side of the handle that still grab it rather than jumping
+const HANDLE_TOLERANCE: f32 = 6.0;
+
+// width of the "Off" region left of the track
+const OFF_WIDTH: f32 = 24.0;
+
+// width of the value box in a label column layout
+const VALUE_COLUMN_WIDTH: f32 = 56.0;
+
+// seconds without edits before the delta readout disappears
+const DELTA_IDLE: f64 = 1.5;
+
+// distance in points the pointer must travel before the value follows it when `click_jumps` is off
+const DRAG_THRESHOLD: f32 = 3.0;
+
+// height of the scrub strip below the track
+const SCRUB_HEIGHT: f32 = 5.0;
+
 impl<'a, T> Slider<'a, T> {
   pub fn from_get_set(range: std::ops::RangeInclusive<T>, get_set_value: impl 'a + FnMut(Option<T>) -> T) -> Self {
-    Self { range, get_set_value: Box::new(get_set_value), title: None }
+    Self {
+      range,
+      get_set_value: Box::new(get_set_value),
+      title: None,
+      click_jumps: true,
+      drag_speed: 0.1,
+      speed_modifier: (Modifiers::NONE, 1.0),
+      selected: None,
+      history: 0,
+      style: SliderStyle::default(),
+      autofit_range: false,
+      transform: None,
+      indeterminate: false,
+      target: None,
+      tint: None,
+      show_delta: false,
+      segments: Vec::new(),
+      unified: false,
+      off_value: None,
+      commit_on_release: false,
+      reversed: false,
+      quantize_input: false,
+      range_presets: Vec::new(),
+      gauge: None,
+      readout: None,
+      scrub_strip: false,
+      scale: SliderScale::Linear,
+    }
   }
 
   pub fn with_title(mut self, title: &'a str) -> Self {
     self.title = Some(title);
     self
   }
+
+  pub fn drag_speed(mut self, speed: f64) -> Self {
+    self.drag_speed = speed;
+    self
+  }
+
+  /// Multiplies `drag_speed` by `multiplier` for as long as `modifier` is held.
+  pub fn speed_modifier(mut self, modifier: Modifiers, multiplier: f64) -> Self {
+    self.speed_modifier = (modifier, multiplier);
+    self
+  }
+
+  /// Lets shift-clicking the title toggle `selected`, and outlines the track while it is set.
+  pub fn selectable(mut self, selected: &'a mut bool) -> Self {
+    self.selected = Some(selected);
+    self
+  }
+
+  /// Paints a sparkline of the last `n` values across the track.
+  pub fn history(mut self, n: usize) -> Self {
+    self.history = n;
+    self
+  }
+
+  /// Width of the draggable handle. Pressing on it (give or take a few points) drags it
+  /// relative to where it was grabbed, while pressing elsewhere on the track jumps.
+  pub fn handle_width(mut self, w: f32) -> Self {
+    self.style.cursor_width = w;
+    self
+  }
+
+  /// Replaces the whole visual style, e.g. one shared by every slider of a panel.
+  pub fn style(mut self, style: SliderStyle) -> Self {
+    self.style = style;
+    self
+  }
+
+  /// Grows the range to include any value committed outside it, e.g. by an animation or a
+  /// loaded file, instead of pinning the cursor at the end of the track. The grown range
+  /// is remembered per widget, only ever expands, and is what track drags map onto.
+  /// Numbers and expressions typed over the track are clamped into the range unless auto-fit
+  /// is on; ones typed into the drag value are kept as-is either way.
+  pub fn autofit_range(mut self, on: bool) -> Self {
+    self.autofit_range = on;
+    self
+  }
+
+  /// Shows and edits the value in display units while storing it in other units, e.g.
+  /// degrees on screen for an angle stored in radians. `range`, the readout and dragging
+  /// all work in display units; only what goes through `get_set_value` is converted.
+  pub fn transform(mut self, to_display: impl 'a + Fn(f64) -> f64, from_display: impl 'a + Fn(f64) -> f64) -> Self {
+    self.transform = Some((Box::new(to_display), Box::new(from_display)));
+    self
+  }
+
+  /// Shows a shimmering track and a placeholder readout and ignores input, for values that
+  /// are still being fetched. The current value is neither shown nor written back.
+  pub fn indeterminate(mut self, on: bool) -> Self {
+    self.indeterminate = on;
+    self
+  }
+
+  /// Colors the track and cursor, e.g. to color-code related controls. `None` keeps the
+  /// neutral look.
+  pub fn tint(mut self, color: Option<Color32>) -> Self {
+    self.tint = color;
+    self
+  }
+
+  /// Shows how far the value moved since editing started, e.g. "+0.050", fading out once
+  /// it has been left alone for a moment.
+  pub fn show_delta(mut self, on: bool) -> Self {
+    self.show_delta = on;
+    self
+  }
+
+  /// Maps the track piecewise-linearly through `(position, value)` breakpoints, positions
+  /// going from 0 to 1 along the track, e.g. `[(0.0, 0.0), (0.5, 1.0), (1.0, 100.0)]` gives
+  /// the first half of the track to 0..1. Values must increase with position. Replaces
+  /// the range's mapping, except while auto-fit is on.
+  pub fn segments(mut self, breakpoints: &[(f32, T)]) -> Self where T: Numeric {
+    self.segments = breakpoints.iter().map(|(position, value)| (*position, value.to_f64())).collect();
+    self.segments.sort_by(|a, b| a.0.total_cmp(&b.0));
+    self
+  }
+
+  /// Spreads values along the track by `scale`; the readout still shows the value itself.
+  /// A logarithmic scale needs a range above zero: over a range that reaches zero or below
+  /// the slider is left linear.
+  pub fn with_scale(mut self, scale: SliderScale) -> Self {
+    let (start, end) = (self.range.start().to_f64(), self.range.end().to_f64());
+    if scale == SliderScale::Logarithmic && start.min(end) <= 0.0 {
+      return self;
+    }
+    self.scale = scale;
+    self
+  }
+
+  /// Draws the number inside the track instead of in a separate drag value, with the
+  /// track filled up to the cursor. Dragging the strip changes the value and double-clicking
+  /// it types one in. A click without dragging never jumps in this mode.
+  pub fn unified(mut self, on: bool) -> Self {
+    self.unified = on;
+    self
+  }
+
+  /// Adds an "Off" region left of the track. Dragging into it, or typing "off", commits
+  /// exactly `v`, a sentinel outside the range the shader treats as disabled.
+  pub fn off_value(mut self, v: T) -> Self {
+    self.off_value = Some(v);
+    self
+  }
+
+  /// While the track is being dragged, shows the live value but only writes it back once
+  /// the pointer is released, for values with expensive side effects. Typed or dragged
+  /// edits in the drag value still write back immediately.
+  pub fn commit_on_release(mut self, on: bool) -> Self {
+    self.commit_on_release = on;
+    self
+  }
+
+  /// Puts the start of the range on the right, so the value grows leftwards. The "Off"
+  /// region and the unified fill move to the right with it.
+  pub fn reversed(mut self, on: bool) -> Self {
+    self.reversed = on;
+    self
+  }
+
+  /// Adds a thin strip below the track for relative scrubbing: dragging anywhere on it
+  /// moves the value by the pointer's travel times `drag_speed`, wherever it started,
+  /// while the track above keeps setting the value from the absolute position.
+  pub fn scrub_strip(mut self, on: bool) -> Self {
+    self.scrub_strip = on;
+    self
+  }
+
+  /// Rounds typed values to the precision the readout shows, so typing `0.1234567` gives
+  /// back `0.123`. The stored value is still an f32 for float controls, so a decimal like
+  /// 0.1 is only ever approximated; this just keeps the typed digits from promising more.
+  pub fn quantize_input(mut self, on: bool) -> Self {
+    self.quantize_input = on;
+    self
+  }
+
+  /// Adds a dropdown switching the range between `presets`, e.g. a fine and a coarse one.
+  /// Switching clamps the value into the new range, unless auto-fit is on. The choice is
+  /// kept in egui's persisted memory; the first preset is used until one is picked.
+  pub fn range_presets(mut self, presets: &[(&'a str, std::ops::RangeInclusive<T>)]) -> Self where T: Numeric {
+    self.range_presets = presets.to_vec();
+    self
+  }
+
+  /// Fills the track up to a measured `v`, e.g. the current exposure, behind the cursor
+  /// so the setpoint can be compared against it. Feed it a fresh value every frame.
+  pub fn gauge_value(mut self, v: T) -> Self {
+    self.gauge = Some(v);
+    self
+  }
+
+  /// Marks `value` on the track as a reference to aim for. Unlike the cursor it can't be
+  /// dragged and nothing snaps to it.
+  pub fn target(mut self, value: T) -> Self {
+    self.target = Some(value);
+    self
+  }
+
+  /// Replaces how the drag value formats the value and parses typed text, e.g. to show
+  /// `1 << v` for a slider over exponents. `parse` returns `None` to reject the text.
+  pub fn readout(mut self, format: impl 'a + Fn(f64) -> String, parse: impl 'a + Fn(&str) -> Option<f64>) -> Self {
+    self.readout = Some((Box::new(format), Box::new(parse)));
+    self
+  }
+
+  /// When off, a click on the track without dragging leaves the value untouched.
+  pub fn click_jumps(mut self, on: bool) -> Self {
+    self.click_jumps = on;
+    self
+  }
+}
+
+// where `value` sits along a track mapped through `segments`, extrapolating past the ends
+fn value_to_position(segments: &[(f32, f64)], value: f64) -> f64 {
+  let last = segments.len() - 2;
+  let i = segments[..=last].iter().position(|&(_, v)| value < v).map_or(last, |i| i.saturating_sub(1));
+  let ((p0, v0), (p1, v1)) = (segments[i], segments[i + 1]);
+  if v1 == v0 { return p0 as f64; }
+  p0 as f64 + (p1 - p0) as f64 * (value - v0) / (v1 - v0)
+}
+
+// the value at `position` along a track mapped through `segments`
+fn position_to_value(segments: &[(f32, f64)], position: f32) -> f64 {
+  let last = segments.len() - 2;
+  let i = segments[..=last].iter().position(|&(p, _)| position < p).map_or(last, |i| i.saturating_sub(1));
+  let ((p0, v0), (p1, v1)) = (segments[i], segments[i + 1]);
+  if p1 == p0 { return v0; }
+  v0 + (v1 - v0) * ((position - p0) / (p1 - p0)) as f64
+}
+
+impl<'a, T: Numeric> Slider<'a, T> {
+  fn format_value(&self, r: f64) -> String {
+    if self.off_value.map_or(false, |off| off.to_f64() == r) {
+      return "Off".to_string();
+    }
+    match &self.readout {
+      Some((format, _)) => format(r),
+      None => format_number(r),
+    }
+  }
+
+  fn parse_value(&self, text: &str) -> Option<f64> {
+    if let (Some(off), true) = (self.off_value, text.trim().eq_ignore_ascii_case("off")) {
+      return Some(off.to_f64());
+    }
+    let parsed = match &self.readout {
+      Some((_, parse)) => parse(text),
+      None => parse_typed(text),
+    };
+    match (parsed, self.quantize_input) {
+      (Some(v), true) => self.format_value(v).parse().ok().or(Some(v)),
+      _ => parsed,
+    }
+  }
+}
+
+fn format_number(r: f64) -> String {
+  if r.abs() < 1.0 {
+    format!("{:.3}", r)
+  } else if r.abs() < 10.0 {
+    format!("{:.2}", r)
+  } else if r.abs() < 100.0 {
+    format!("{:.1}", r)
+  } else {
+    format!("{:.0}", r)
+  }
+}
+
+// a plain number or an arithmetic expression, neither clamped: the caller applies its own range rule
+fn parse_typed(text: &str) -> Option<f64> {
+  text.trim().parse::<f64>().ok().or_else(|| expr::eval(text))
 }
 
 impl<'a, T: Numeric> Widget for Slider<'a, T> {
   fn ui(mut self, ui: &mut eframe::egui::Ui) -> eframe::egui::Response {
     let mut available_size = ui.available_size_before_wrap();
-    available_size.y = 20.0;
-    ui.allocate_ui_with_layout(available_size, Layout::right_to_left(eframe::egui::Align::Center), |ui| {
+    available_size.y = self.style.track_height;
+    let inner = ui.allocate_ui_with_layout(available_size, Layout::right_to_left(eframe::egui::Align::Center), |ui| {
       // 1.draw the drag value
-      let mut value = (self.get_set_value)(None);
-      ui.add_sized(vec2(0.0, available_size.y),DragValue::new(&mut value).speed(0.1).custom_formatter(|r, _| {
-        if r.abs() < 1.0 {
-          format!("{:.3}", r)
-        } else if r.abs() < 10.0 {
-          format!("{:.2}", r)
-        } else if r.abs() < 100.0 {
-          format!("{:.1}", r)
-        } else {
-          format!("{:.0}", r)
+      let stored = (self.get_set_value)(None);
+      let displayed = match &self.transform {
+        Some((to_display, _)) => T::from_f64(to_display(stored.to_f64())),
+        None => stored,
+      };
+      // the live value of a drag whose write-back waits for the release
+      let pending_id = ui.id().with("pending");
+      let pending = if self.commit_on_release { ui.memory_mut(|m| m.data.get_temp::<f64>(pending_id)) } else { None };
+      let mut value = pending.map_or(displayed, T::from_f64);
+      let mut edited_directly = false;
+      let (modifier, multiplier) = self.speed_modifier;
+      let speed = if ui.input(|i| i.modifiers.contains(modifier)) { self.drag_speed * multiplier } else { self.drag_speed };
+      let value_width = if self.style.label_column { VALUE_COLUMN_WIDTH } else { 0.0 };
+      if self.indeterminate {
+        ui.add_sized(vec2(value_width, available_size.y), Label::new("—"));
+      } else if !self.unified {
+        let drag_value = DragValue::new(&mut value).speed(speed)
+          .custom_formatter(|r, _| self.format_value(r))
+          .custom_parser(|text| self.parse_value(text));
+        edited_directly = ui.add_sized(vec2(value_width, available_size.y), drag_value).changed();
+      }
+
+      if !self.range_presets.is_empty() {
+        let preset_id = ui.id().with("range_preset");
+        let saved = ui.data_mut(|d| d.get_persisted::<usize>(preset_id)).unwrap_or(0).min(self.range_presets.len() - 1);
+        let mut selected = saved;
+        ComboBox::from_id_salt(preset_id).width(60.0).selected_text(self.range_presets[selected].0).show_ui(ui, |ui| {
+          for (i, (label, _)) in self.range_presets.iter().enumerate() {
+            ui.selectable_value(&mut selected, i, *label);
+          }
+        });
+        self.range = self.range_presets[selected].1.clone();
+        if selected != saved {
+          ui.data_mut(|d| d.insert_persisted(preset_id, selected));
+          if !self.autofit_range {
+            let (start, end) = (self.range.start().to_f64(), self.range.end().to_f64());
+            value = T::from_f64(value.to_f64().clamp(start.min(end), start.max(end)));
+          }
         }
-      }));
+      }
 
       let mut available_size = ui.available_size_before_wrap();
       available_size.x = available_size.x.max(ui.style().spacing.slider_width);
       if self.title.is_some() {
-        available_size.x -= 108.0;
+        available_size.x -= self.style.title_width + 8.0;
       }
 
+      let sense = if self.indeterminate { Sense::hover() } else { Sense::click_and_drag() };
       let (mut response, painter) = ui
-        .allocate_painter(available_size, Sense::click_and_drag());
-      response = response.on_hover_cursor(eframe::egui::CursorIcon::PointingHand);
+        .allocate_painter(available_size, sense);
+      if !self.indeterminate {
+        response = response.on_hover_cursor(eframe::egui::CursorIcon::PointingHand);
+      }
 
       let rect = painter.clip_rect();
+      let (rect, scrub_rect) = match self.scrub_strip && !self.indeterminate {
+        true => {
+          let strip = Rect::from_min_max(pos2(rect.min.x, rect.max.y - SCRUB_HEIGHT), rect.max);
+          (Rect::from_min_max(rect.min, pos2(rect.max.x, strip.min.y - 1.0)), Some(strip))
+        },
+        false => (rect, None),
+      };
       let size = rect.max - rect.min;
 
       // 1. draw background rectangle
@@ -56,37 +439,306 @@ impl<'a, T: Numeric> Widget for Slider<'a, T> {
         if ui.visuals().dark_mode { "01" } else { "06" }
       };
       let tint = if ui.visuals().dark_mode { "FFFFFF" } else { "000000" };
-      painter.rect_filled(rect, 4.0, Color32::from_hex(format!("#{}{}", tint, background_alpha).as_str()).unwrap());
+      let background = match self.tint {
+        Some(color) => color.gamma_multiply(if response.hovered() { 0.25 } else { 0.15 }),
+        None => Color32::from_hex(format!("#{}{}", tint, background_alpha).as_str()).unwrap(),
+      };
+      painter.rect_filled(rect, self.style.corner_radius, background);
 
       if let Some(title) = self.title {
-        ui.allocate_ui_with_layout(vec2(100.0, 20.0), Layout::left_to_right(eframe::egui::Align::Center), |ui| {
-          ui.label(title);
+        ui.allocate_ui_with_layout(vec2(self.style.title_width, self.style.track_height), Layout::left_to_right(eframe::egui::Align::Center), |ui| {
+          let label = match self.style.label_column {
+            true => ui.add(Label::new(title).truncate().sense(Sense::click())),
+            false => ui.add(Label::new(title).sense(Sense::click())),
+          };
+          if let Some(selected) = &mut self.selected {
+            if label.clicked() && ui.input(|i| i.modifiers.shift) {
+              **selected = !**selected;
+            }
+          }
         });
       }
+      if self.selected.as_deref() == Some(&true) {
+        painter.rect_stroke(rect, self.style.corner_radius, Stroke::new(1.0, ui.visuals().selection.stroke.color));
+      }
+
+      // a band sweeping across the track until the value arrives
+      if self.indeterminate {
+        let t = (ui.input(|i| i.time) * 0.8).fract() as f32;
+        let band = size.x * 0.3;
+        let x = rect.min.x - band + t * (size.x + band);
+        let shimmer = Rect::from_min_size(pos2(x, rect.min.y), vec2(band, size.y)).intersect(rect);
+        let shimmer_alpha = if ui.visuals().dark_mode { "0C" } else { "14" };
+        painter.rect_filled(shimmer, self.style.corner_radius, Color32::from_hex(format!("#{}{}", tint, shimmer_alpha).as_str()).unwrap());
+        ui.ctx().request_repaint();
+        return response;
+      }
+      // an "Off" region left of the track, the rest of the slider works on what remains
+      let off_rect = self.off_value.map(|_| match self.reversed {
+        false => Rect::from_min_size(rect.min, vec2(OFF_WIDTH, size.y)),
+        true => Rect::from_min_max(pos2(rect.max.x - OFF_WIDTH, rect.min.y), rect.max),
+      });
+      let is_off = self.off_value.map_or(false, |off| off == value);
+      let rect = match (off_rect, self.reversed) {
+        (Some(off_rect), false) => Rect::from_min_max(pos2(off_rect.max.x, rect.min.y), rect.max),
+        (Some(off_rect), true) => Rect::from_min_max(rect.min, pos2(off_rect.min.x, rect.max.y)),
+        (None, _) => rect,
+      };
+      let size = rect.max - rect.min;
+      if let Some(off_rect) = off_rect {
+        let off_alpha = if is_off { "30" } else { "10" };
+        painter.rect_filled(off_rect, self.style.corner_radius, Color32::from_hex(format!("#{}{}", tint, off_alpha).as_str()).unwrap());
+        painter.text(off_rect.center(), Align2::CENTER_CENTER, "Off", FontId::proportional(10.0), ui.visuals().weak_text_color());
+      }
+
       // 2. draw the cursor
-      let cursor_size = vec2(4.0, size.y);
-      let start = self.range.start().to_f64();
-      let end = self.range.end().to_f64();
+      let cursor_size = vec2(self.style.cursor_width, size.y);
+      let mut start = self.range.start().to_f64();
+      let mut end = self.range.end().to_f64();
       let v = value.to_f64();
-      let mut per = (v - start) / (end - start);
+      if self.autofit_range && !is_off {
+        (start, end) = ui.memory_mut(|m| {
+          let fit = m.data.get_temp_mut_or_insert_with(response.id.with("autofit"), || (start, end));
+          fit.0 = fit.0.min(start).min(v);
+          fit.1 = fit.1.max(end).max(v);
+          *fit
+        });
+      }
+      let segments = if self.segments.len() >= 2 && !self.autofit_range { self.segments.clone() } else { vec![(0.0, start), (1.0, end)] };
+      // a logarithmic track is linear in log space, with values at or below zero pinned to its start
+      let log = self.scale == SliderScale::Logarithmic && segments.iter().all(|(_, v)| *v > 0.0);
+      let floor = segments.iter().map(|(_, v)| *v).fold(f64::INFINITY, f64::min);
+      let scale = move |v: f64| if log { v.max(floor).ln() } else { v };
+      let segments: Vec<(f32, f64)> = segments.into_iter().map(|(p, v)| (p, scale(v))).collect();
+      let reversed = self.reversed;
+      let to_per = |v: f64| if reversed { 1.0 - value_to_position(&segments, scale(v)) } else { value_to_position(&segments, scale(v)) };
+      let from_per = |per: f32| {
+        let v = position_to_value(&segments, if reversed { 1.0 - per } else { per });
+        if log { v.exp() } else { v }
+      };
+      let mut per = to_per(v);
       
       per = per.min(1.0).max(0.0);
-      let pos = rect.min + vec2((per as f32) * (size.x - cursor_size.x), 0.0);
+      let pos = match off_rect {
+        Some(off_rect) if is_off => pos2(off_rect.center().x - cursor_size.x / 2.0, rect.min.y),
+        _ => rect.min + vec2((per as f32) * (size.x - cursor_size.x), 0.0),
+      };
       let cursor_rect = Rect { min: pos, max: pos + cursor_size };
+
+      // the measured value, behind the cursor
+      if let Some(gauge) = self.gauge {
+        let x = rect.min.x + to_per(gauge.to_f64()).min(1.0).max(0.0) as f32 * size.x;
+        let gauge_rect = match self.reversed {
+          false => Rect::from_min_max(pos2(rect.min.x, rect.max.y - size.y * 0.4), pos2(x, rect.max.y)),
+          true => Rect::from_min_max(pos2(x, rect.max.y - size.y * 0.4), rect.max),
+        };
+        let gauge_color = ui.visuals().selection.bg_fill.gamma_multiply(0.5);
+        painter.rect_filled(gauge_rect, 2.0, gauge_color);
+      }
+
+      // the filled part of a unified strip, up to the cursor
+      if self.unified {
+        let fill = match self.reversed {
+          false => Rect::from_min_max(rect.min, pos2(cursor_rect.center().x, rect.max.y)),
+          true => Rect::from_min_max(pos2(cursor_rect.center().x, rect.min.y), rect.max),
+        };
+        let fill_color = match self.tint {
+          Some(color) => color.gamma_multiply(0.35),
+          None => Color32::from_hex(format!("#{}{}", tint, if ui.visuals().dark_mode { "14" } else { "1C" }).as_str()).unwrap(),
+        };
+        painter.rect_filled(fill, self.style.corner_radius, fill_color);
+      }
+
       let cursor_alpha = if response.hovered() { 
         if ui.visuals().dark_mode { "FF" } else { "CC" }
       } else {
         if ui.visuals().dark_mode { "0A" } else { "18" }
       };
-      painter.rect_filled(cursor_rect, 4.0, Color32::from_hex(format!("#{}{}", tint, cursor_alpha).as_str()).unwrap());
+      let cursor = match self.tint {
+        Some(color) => color.gamma_multiply(if response.hovered() { 1.0 } else { 0.5 }),
+        None => Color32::from_hex(format!("#{}{}", tint, cursor_alpha).as_str()).unwrap(),
+      };
+      painter.rect_filled(cursor_rect, self.style.corner_radius, cursor);
+
+      // a thin accent bar at the target, over the cursor so it stays visible when they meet
+      if let Some(target) = self.target {
+        let per = to_per(target.to_f64()).min(1.0).max(0.0) as f32;
+        let x = rect.min.x + cursor_size.x / 2.0 + per * (size.x - cursor_size.x);
+        let color = ui.visuals().selection.stroke.color;
+        painter.rect_filled(Rect::from_min_max(pos2(x - 1.0, rect.min.y + 2.0), pos2(x + 1.0, rect.max.y - 2.0)), 1.0, color);
+      }
+
+      // a text field over the track while typing a value in after a double-click, or else
+      // the number inside a unified strip
+      let edit_id = response.id.with("edit");
+      if response.double_clicked() {
+        let text = self.format_value(value.to_f64());
+        ui.memory_mut(|m| m.data.insert_temp(edit_id, text));
+      }
+
+      match ui.memory_mut(|m| m.data.get_temp::<String>(edit_id)) {
+        Some(mut text) => {
+          let edit = ui.put(rect, TextEdit::singleline(&mut text).id(edit_id.with("field")));
+          if edit.lost_focus() {
+            ui.memory_mut(|m| m.data.remove::<String>(edit_id));
+            let parsed = self.parse_value(&text);
+            let cancelled = ui.input(|i| i.key_pressed(Key::Escape));
+            if let Some(parsed) = parsed.filter(|_| !cancelled) {
+              let is_off = self.off_value.map_or(false, |off| off.to_f64() == parsed);
+              let parsed = if self.autofit_range || is_off { parsed } else { parsed.clamp(start.min(end), start.max(end)) };
+              value = T::from_f64(parsed);
+            }
+          } else {
+            if !edit.has_focus() { edit.request_focus(); }
+            ui.memory_mut(|m| m.data.insert_temp(edit_id, text));
+          }
+        },
+        None if self.unified => {
+          let text = self.format_value(value.to_f64());
+          painter.text(rect.center(), Align2::CENTER_CENTER, text, FontId::proportional(12.0), ui.visuals().text_color());
+        },
+        None => {},
+      }
+
+      let click_jumps = self.click_jumps && !self.unified;
+      let drag_id = response.id.with("drag_distance");
+      let follow = if !response.is_pointer_button_down_on() {
+        if !click_jumps { ui.memory_mut(|m| m.data.remove::<f32>(drag_id)); }
+        false
+      } else if click_jumps {
+        true
+      } else {
+        let distance = ui.memory_mut(|m| {
+          let distance = m.data.get_temp_mut_or_default::<f32>(drag_id);
+          *distance += response.drag_delta().length();
+          *distance
+        });
+        distance >= DRAG_THRESHOLD
+      };
 
-      if response.is_pointer_button_down_on() {
-        let mut per = (response.interact_pointer_pos().unwrap().x - rect.min.x) / size.x;
+      let grab_id = response.id.with("grab");
+      let grab = if response.is_pointer_button_down_on() {
+        let handle_rect = cursor_rect.expand2(vec2(HANDLE_TOLERANCE, 0.0));
+        let grab = ui.input(|i| i.pointer.press_origin())
+          .filter(|origin| handle_rect.contains(*origin))
+          .map(|origin| origin.x - cursor_rect.min.x);
+        ui.memory_mut(|m| *m.data.get_temp_mut_or_insert_with(grab_id, || grab))
+      } else {
+        ui.memory_mut(|m| m.data.remove::<Option<f32>>(grab_id));
+        None
+      };
+
+      let in_off = |x: f32| off_rect.map_or(false, |off_rect| if reversed { x > off_rect.min.x } else { x < off_rect.max.x });
+      if let Some(offset) = grab {
+        if response.dragged() {
+          let pointer = response.interact_pointer_pos().unwrap().x;
+          let x = pointer - offset;
+          let per = ((x - rect.min.x) / (size.x - cursor_size.x)).min(1.0).max(0.0);
+          value = match self.off_value {
+            Some(off) if in_off(pointer) => off,
+            _ => T::from_f64(from_per(per)),
+          };
+        }
+      } else if follow {
+        let pointer = response.interact_pointer_pos().unwrap().x;
+        let mut per = (pointer - rect.min.x) / size.x;
         per = per.min(1.0).max(0.0);
-        value = T::from_f64(start + (end - start) * per as f64);
+        value = match self.off_value {
+          Some(off) if in_off(pointer) => off,
+          _ => T::from_f64(from_per(per)),
+        };
+      }
+
+      if let Some(scrub_rect) = scrub_rect {
+        let scrub_id = response.id.with("scrub");
+        let scrub = ui.interact(scrub_rect, scrub_id, Sense::drag()).on_hover_cursor(eframe::egui::CursorIcon::ResizeHorizontal);
+        let scrub_alpha = if scrub.hovered() || scrub.dragged() { "28" } else { "0C" };
+        painter.rect_filled(scrub_rect, 2.0, Color32::from_hex(format!("#{}{}", tint, scrub_alpha).as_str()).unwrap());
+        if scrub.dragged() {
+          // accumulated unrounded, so integer sliders still move on slow drags
+          let direction = if reversed { -1.0 } else { 1.0 };
+          let scrubbed = ui.memory_mut(|m| {
+            let scrubbed = m.data.get_temp_mut_or_insert_with(scrub_id, || value.to_f64());
+            *scrubbed = (*scrubbed + direction * scrub.drag_delta().x as f64 * speed).clamp(start.min(end), start.max(end));
+            *scrubbed
+          });
+          value = T::from_f64(scrubbed);
+        } else {
+          ui.memory_mut(|m| m.data.remove::<f64>(scrub_id));
+        }
+        response = response | scrub;
+      }
+
+      // 3. draw the history sparkline
+      if self.history > 0 {
+        let len = self.history;
+        let history = ui.memory_mut(|m| {
+          let history = m.data.get_temp_mut_or_default::<VecDeque<f64>>(response.id.with("history"));
+          history.push_back(value.to_f64());
+          while history.len() > len { history.pop_front(); }
+          history.clone()
+        });
+
+        if history.len() > 1 {
+          let step = size.x / (len - 1).max(1) as f32;
+          let points = history.iter().enumerate().map(|(i, v)| {
+            let per = value_to_position(&segments, scale(*v)).min(1.0).max(0.0) as f32;
+            pos2(rect.min.x + i as f32 * step, rect.max.y - per * size.y)
+          }).collect();
+          painter.add(Shape::line(points, Stroke::new(1.0, Color32::from_hex(format!("#{}40", tint).as_str()).unwrap())));
+        }
+      }
+
+      // 4. draw the delta since editing started
+      if self.show_delta {
+        let delta_id = response.id.with("delta");
+        let now = ui.input(|i| i.time);
+        let (base, changed_at) = ui.memory_mut(|m| {
+          let entry = m.data.get_temp_mut_or_insert_with(delta_id, || (displayed.to_f64(), now));
+          if value != displayed { entry.1 = now; }
+          *entry
+        });
+        let idle = now - changed_at;
+        if idle > DELTA_IDLE {
+          ui.memory_mut(|m| m.data.remove::<(f64, f64)>(delta_id));
+        } else if value.to_f64() != base {
+          let alpha = (1.0 - idle / DELTA_IDLE) as f32;
+          let color = ui.visuals().text_color().gamma_multiply(alpha);
+          let text = format!("{:+.3}", value.to_f64() - base);
+          painter.text(rect.right_center() - vec2(4.0, 0.0), Align2::RIGHT_CENTER, text, FontId::monospace(10.0), color);
+          ui.ctx().request_repaint();
+        }
+      }
+
+      if self.commit_on_release {
+        if response.is_pointer_button_down_on() && !edited_directly {
+          ui.memory_mut(|m| m.data.insert_temp(pending_id, value.to_f64()));
+          return response;
+        }
+        ui.memory_mut(|m| m.data.remove::<f64>(pending_id));
       }
 
+      // only convert back when edited, so the round trip can't drift the stored value
+      let value = match &self.transform {
+        Some((_, from_display)) if value != displayed => T::from_f64(from_display(value.to_f64())),
+        Some(_) => stored,
+        None => value,
+      };
       (self.get_set_value)(Some(value));
-    }).response
+      response
+    });
+    // include the track's clicks, so callers can hang context menus off the slider
+    inner.response | inner.inner
   }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn numbers_and_expressions_parse_alike() {
+    assert_eq!(parse_typed("8"), Some(8.0));
+    assert_eq!(parse_typed("2 * 4"), Some(8.0));
+    assert_eq!(parse_typed(" -3 "), parse_typed("1 - 4"));
+  }
+}
@@ -0,0 +1,159 @@
+use crate::{controls::{ControlDiff, ControlGroup, ControlValue, Controls}, error::ControlError, serialize::leak};
+
+// floats that tend to break encoders: extremes, subnormals, signed zero and awkward decimals
+const EDGE_FLOATS: [f32; 8] = [0.0, -0.0, f32::MAX, f32::MIN, f32::MIN_POSITIVE, 1.0e-40, 0.1, -1.0 / 3.0];
+
+// xorshift64*, so a failing seed replays the same panel everywhere
+struct Rng(u64);
+
+impl Rng {
+  fn next(&mut self) -> u64 {
+    self.0 ^= self.0 >> 12;
+    self.0 ^= self.0 << 25;
+    self.0 ^= self.0 >> 27;
+    self.0.wrapping_mul(0x2545F4914F6CDD1D)
+  }
+
+  fn below(&mut self, n: u64) -> u64 {
+    self.next() % n
+  }
+
+  fn float(&mut self) -> f32 {
+    match self.below(4) {
+      0 => EDGE_FLOATS[self.below(EDGE_FLOATS.len() as u64) as usize],
+      _ => (self.next() as f64 / u64::MAX as f64 * 2000.0 - 1000.0) as f32,
+    }
+  }
+
+  fn floats<const N: usize>(&mut self) -> [f32; N] {
+    std::array::from_fn(|_| self.float())
+  }
+
+  fn ints<const N: usize>(&mut self) -> [i32; N] {
+    std::array::from_fn(|_| self.next() as i32)
+  }
+}
+
+/// Builds a panel from `seed` with random groups, some of them empty, holding controls of
+/// every type with random values in a random order, for fuzzing the save formats. Values
+/// are finite but include extremes, subnormals and negative zero. Names are leaked.
+pub fn random_controls(seed: u64) -> Controls {
+  let mut rng = Rng(seed.max(1));
+  let mut controls = Controls::new();
+  for g in 0..rng.below(6) {
+    let name = leak(&format!("group{}", g));
+    let mut group = ControlGroup::new(name);
+    for _ in 0..rng.below(12) {
+      let control = leak(&format!("c{}", rng.below(1000)));
+      if group.values.get(control).is_some() { continue; }
+      let value = match rng.below(13) {
+        0 => ControlValue::from(rng.next() as i32),
+        1 => ControlValue::from(rng.below(2) == 1),
+        2 => ControlValue::from(rng.float()),
+        3 => ControlValue::from(rng.floats::<2>()),
+        4 => ControlValue::from(rng.floats::<3>()),
+        5 => ControlValue::from(rng.floats::<4>()),
+        6 => ControlValue::from((0..1 + rng.below(4)).map(|_| (rng.float(), rng.floats::<4>())).collect::<Vec<_>>()),
+        7 => ControlValue::from([rng.floats::<3>(), rng.floats::<3>(), rng.floats::<3>()]),
+        8 => ControlValue::from([rng.floats::<4>(), rng.floats::<4>(), rng.floats::<4>(), rng.floats::<4>()]),
+        9 => ControlValue::from(rng.ints::<2>()),
+        10 => ControlValue::from(rng.ints::<3>()),
+        11 => ControlValue::from(rng.ints::<4>()),
+        _ => {
+          let points = (0..2 + rng.below(4)).map(|_| rng.floats::<2>()).collect();
+          ControlValue::from((points, 4 * (1 + rng.below(16) as usize)))
+        },
+      };
+      group.commit(control, value);
+    }
+    controls.values.insert(name, group);
+  }
+  controls
+}
+
+/// Saves `controls` as JSON and as bytes, loads each back, and returns what differs after
+/// each, JSON first. Both are empty when the formats round-trip. Bytes load onto a copy of
+/// the panel with every value zeroed, so they are checked independently of JSON.
+pub fn round_trip(controls: &Controls) -> Result<(Vec<ControlDiff>, Vec<ControlDiff>), ControlError> {
+  let json = serde_json::to_string(controls).map_err(|e| ControlError::ParseError(e.to_string()))?;
+  let from_json: Controls = serde_json::from_str(&json).map_err(|e| ControlError::ParseError(e.to_string()))?;
+
+  let mut from_bytes = Controls::new();
+  for (&name, group) in &controls.values {
+    let mut zeroed = ControlGroup::new(name);
+    for (&control, value) in &group.values {
+      let zero = (0..value.components().len()).fold(value.clone(), |v, i| v.with_component(i, 0.0).unwrap());
+      zeroed.commit(control, zero);
+    }
+    from_bytes.values.insert(name, zeroed);
+  }
+  from_bytes.from_bytes(&controls.to_bytes())?;
+
+  Ok((controls.diff(&from_json), controls.diff(&from_bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // `controls` with every group declared again, its controls in reverse order and zeroed
+  fn declared_reversed(controls: &Controls) -> Controls {
+    let mut declared = Controls::new();
+    for (&name, group) in &controls.values {
+      let mut zeroed = ControlGroup::new(name);
+      for &control in group.values.order.iter().rev() {
+        let value = group.values.get(control).unwrap();
+        let zero = (0..value.components().len()).fold(value.clone(), |v, i| v.with_component(i, 0.0).unwrap());
+        zeroed.commit(control, zero);
+      }
+      declared.values.insert(name, zeroed);
+    }
+    declared
+  }
+
+  #[test]
+  fn random_panels_round_trip() {
+    for seed in 1..=500 {
+      let controls = random_controls(seed);
+      let (json, bytes) = round_trip(&controls).unwrap();
+      assert!(json.is_empty(), "seed {}: JSON changed {:?}", seed, json);
+      assert!(bytes.is_empty(), "seed {}: bytes changed {:?}", seed, bytes);
+    }
+  }
+
+  #[test]
+  fn extreme_floats_round_trip() {
+    let mut extremes = ControlGroup::new("extremes");
+    for (i, &f) in EDGE_FLOATS.iter().enumerate() {
+      let name = |kind: &str| leak(&format!("{}{}", kind, i));
+      extremes.commit(name("float"), ControlValue::from(f));
+      extremes.commit(name("vec4"), ControlValue::from([f, -f, f, -f]));
+      extremes.commit(name("mat3"), ControlValue::from([[f; 3]; 3]));
+      extremes.commit(name("gradient"), ControlValue::from(vec![(f, [f; 4]), (-f, [-f; 4])]));
+      extremes.commit(name("curve"), ControlValue::from((vec![[f, -f], [-f, f]], 8)));
+    }
+    let mut controls = Controls::new();
+    controls.values.insert("extremes", extremes);
+    controls.values.insert("empty", ControlGroup::new("empty"));
+
+    let (json, bytes) = round_trip(&controls).unwrap();
+    assert!(json.is_empty(), "JSON changed {:?}", json);
+    assert!(bytes.is_empty(), "bytes changed {:?}", bytes);
+    let reloaded: Controls = serde_json::from_str(&serde_json::to_string(&controls).unwrap()).unwrap();
+    assert!(reloaded.values.get("empty").is_some_and(|group| group.values.is_empty()));
+  }
+
+  #[test]
+  fn shuffled_declarations_load_every_value() {
+    for seed in 1..=200 {
+      let controls = random_controls(seed);
+      let mut from_json = declared_reversed(&controls);
+      from_json.from_json(&serde_json::to_string(&controls).unwrap()).unwrap();
+      assert!(controls.diff(&from_json).is_empty(), "seed {}: JSON changed {:?}", seed, controls.diff(&from_json));
+
+      let mut from_bytes = declared_reversed(&controls);
+      from_bytes.from_bytes(&controls.to_bytes()).unwrap();
+      assert!(controls.diff(&from_bytes).is_empty(), "seed {}: bytes changed {:?}", seed, controls.diff(&from_bytes));
+    }
+  }
+}